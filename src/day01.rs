@@ -7,7 +7,7 @@ pub fn max_calories(file: &str, n_elves: usize) -> i32 {
         .split("\n\n")
         .map(|x| {
             x.trim()
-                .split('\n')
+                .lines()
                 .map(|x| x.parse::<i32>().unwrap())
                 .sum::<i32>()
         })
@@ -32,13 +32,13 @@ mod tests {
             ("input/day01.txt", 69693, 1),
             // part 2
             ("example/day01.txt", 45000, 3),
-            ("input/day01.txt", 200945, 3),
+            ("input/day01.txt", 200_945, 3),
         ];
 
         for test in tests {
             let (file, want, n_elves) = test;
             let got = day01::max_calories(file, n_elves);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 }
@@ -186,7 +186,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day02::total_score(file);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 
@@ -212,7 +212,7 @@ mod tests {
         for test in tests {
             let (input, want) = test;
             let got = day02::parse_round_into_desired_outcome(input);
-            assert_eq!(want, got, "want {want:?}, got {got:?}")
+            assert_eq!(want, got, "want {want:?}, got {got:?}");
         }
     }
 
@@ -225,7 +225,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day02::total_score_alternative(file);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 }
@@ -77,7 +77,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day03::count_overlap_priority(file);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 
@@ -90,7 +90,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day03::count_group_priority(file);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 }
@@ -1,47 +1,77 @@
-use std::{collections::HashSet, fs, ops::Not};
+use std::fs;
 
-pub fn count_assignment_overlaps(
-    file: &str,
-    filter_func: fn(&(HashSet<i32>, HashSet<i32>)) -> bool,
-) -> i32 {
+use crate::interval::{Interval, IntervalSet};
+
+// the two elves' section assignments, as inclusive ranges of section IDs
+pub type AssignmentPair = (Interval, Interval);
+
+pub fn count_assignment_overlaps(file: &str, filter_func: fn(&AssignmentPair) -> bool) -> i32 {
     i32::try_from(
-        fs::read_to_string(file)
-            .unwrap()
-            .lines()
-            .map(parse_ranges)
-            .filter(filter_func)
+        parse_assignments(file)
+            .iter()
+            .filter(|pair| filter_func(pair))
             .count(),
     )
     .unwrap()
 }
 
-pub fn has_subset((set_one, set_two): &(HashSet<i32>, HashSet<i32>)) -> bool {
-    set_one.is_subset(set_two) || set_two.is_subset(set_one)
+pub fn has_subset((range_one, range_two): &AssignmentPair) -> bool {
+    range_one.contains_interval(range_two) || range_two.contains_interval(range_one)
 }
 
-pub fn has_overlap((set_one, set_two): &(HashSet<i32>, HashSet<i32>)) -> bool {
-    set_one
-        .intersection(set_two)
-        .collect::<HashSet<&i32>>()
-        .is_empty()
-        .not()
+pub fn has_overlap((range_one, range_two): &AssignmentPair) -> bool {
+    range_one.overlaps(range_two)
 }
 
-fn parse_ranges(line: &str) -> (HashSet<i32>, HashSet<i32>) {
-    let numbers: Vec<i32> = line
+// number of sections both elves in the pair were assigned
+pub fn overlap_amount((range_one, range_two): &AssignmentPair) -> isize {
+    range_one.intersection(range_two).length()
+}
+
+pub fn sum_overlap_amounts(file: &str) -> isize {
+    parse_assignments(file).iter().map(overlap_amount).sum()
+}
+
+// number of distinct sections assigned to at least one elf
+pub fn union_coverage(file: &str) -> isize {
+    parse_assignments(file)
+        .into_iter()
+        .flat_map(|(range_one, range_two)| [range_one, range_two])
+        .collect::<IntervalSet>()
+        .total_length()
+}
+
+pub fn disjoint_pairs(file: &str) -> Vec<AssignmentPair> {
+    parse_assignments(file)
+        .into_iter()
+        .filter(|pair| !has_overlap(pair))
+        .collect()
+}
+
+pub fn parse_assignments(file: &str) -> Vec<AssignmentPair> {
+    fs::read_to_string(file)
+        .unwrap()
+        .lines()
+        .map(parse_ranges)
+        .collect()
+}
+
+fn parse_ranges(line: &str) -> AssignmentPair {
+    let numbers: Vec<isize> = line
         .replace(',', "-")
         .split('-')
         .map(|n| n.parse().unwrap())
         .collect();
     let (start_one, end_one, start_two, end_two) = (numbers[0], numbers[1], numbers[2], numbers[3]);
     (
-        (start_one..=end_one).collect(),
-        (start_two..=end_two).collect(),
+        Interval::new(start_one, end_one),
+        Interval::new(start_two, end_two),
     )
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::interval::Interval;
     use crate::{day04, fetch_input};
 
     #[test]
@@ -69,4 +99,28 @@ mod tests {
             assert_eq!(want, got, "want {want}, got {got}, for {file}");
         }
     }
+
+    #[test]
+    fn assignment_queries() {
+        let file = "example/day04.txt";
+
+        assert_eq!(day04::sum_overlap_amounts(file), 10);
+        assert_eq!(day04::union_coverage(file), 8);
+        assert_eq!(
+            day04::disjoint_pairs(file),
+            vec![
+                (Interval::new(2, 4), Interval::new(6, 8)),
+                (Interval::new(2, 3), Interval::new(4, 5)),
+            ]
+        );
+
+        // huge section IDs are no more expensive than small ones
+        let pair = (
+            Interval::new(1, 1_000_000_000),
+            Interval::new(500_000_000, 2_000_000_000),
+        );
+        assert!(day04::has_overlap(&pair));
+        assert!(!day04::has_subset(&pair));
+        assert_eq!(day04::overlap_amount(&pair), 500_000_001);
+    }
 }
@@ -42,7 +42,7 @@ pub fn find_top_crates(file: &str, one_by_one: bool) -> String {
                 None => {
                     crates.insert(i, vec![item]);
                 }
-            };
+            }
         }
     }
 
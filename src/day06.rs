@@ -24,7 +24,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day06::find_start_marker_position(file, packet_marker_start_size);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 
@@ -38,7 +38,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day06::find_start_marker_position(file, message_marker_start_size);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 }
@@ -177,7 +177,7 @@ pub fn sum_small_dirs(filename: &str) -> u64 {
 
 pub fn delete_directory(filename: &str) -> u64 {
     let mut dirs = collect_directory_data(filename);
-    dirs.sort_by(|dir_a, dir_b| dir_a.size.cmp(&dir_b.size));
+    dirs.sort_by_key(|dir_a| dir_a.size);
 
     let root_dir = dirs.pop().unwrap();
     assert_eq!(root_dir.name, "/");
@@ -212,7 +212,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day07::sum_small_dirs(file);
-            assert_eq!(got, want, "got {}, wanted {}", got, want)
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 
@@ -228,7 +228,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day07::delete_directory(file);
-            assert_eq!(got, want, "got {}, wanted {}", got, want)
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 }
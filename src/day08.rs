@@ -132,7 +132,7 @@ mod tests {
         for test in tests {
             let (filename, want) = test;
             let got = day08::count_visible_trees(filename);
-            assert_eq!(got, want, "got {got}, wanted {want}, for {filename}")
+            assert_eq!(got, want, "got {got}, wanted {want}, for {filename}");
         }
     }
 
@@ -140,12 +140,12 @@ mod tests {
     fn max_scenic_score() {
         fetch_input(8);
 
-        let tests = vec![("example/day08.txt", 8), ("input/day08.txt", 374400)];
+        let tests = vec![("example/day08.txt", 8), ("input/day08.txt", 374_400)];
 
         for test in tests {
             let (filename, want) = test;
             let got = day08::max_scenic_score(filename);
-            assert_eq!(got, want, "got {got}, wanted {want}, for {filename}")
+            assert_eq!(got, want, "got {got}, wanted {want}, for {filename}");
         }
     }
 }
@@ -91,7 +91,7 @@ impl Rope {
                     self.knots[behind_ix].y -= 1;
                 }
             }
-        };
+        }
     }
 
     fn update(&mut self, movement: &Movement, tail_positions: &mut HashSet<Point>) {
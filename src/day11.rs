@@ -1,11 +1,11 @@
-use std::{collections::VecDeque, fs, iter};
+use std::{collections::VecDeque, fs};
 
 struct Monkey {
     items: VecDeque<usize>,
     operation: Box<dyn Fn(usize) -> usize>,
     test_divisor: usize,
-    test_true_monkey: usize,
-    test_false_monkey: usize,
+    test_true_target: usize,
+    test_false_target: usize,
 }
 
 impl Monkey {
@@ -59,7 +59,7 @@ impl Monkey {
             .parse()
             .expect("a number");
 
-        let test_true_monkey: usize = lines
+        let test_true_target: usize = lines
             .next()
             .expect("another line")
             .trim()
@@ -68,7 +68,7 @@ impl Monkey {
             .parse()
             .expect("a number");
 
-        let test_false_monkey: usize = lines
+        let test_false_target: usize = lines
             .next()
             .expect("another line")
             .trim()
@@ -81,8 +81,8 @@ impl Monkey {
             items,
             operation,
             test_divisor,
-            test_true_monkey,
-            test_false_monkey,
+            test_true_target,
+            test_false_target,
         }
     }
 }
@@ -111,7 +111,7 @@ impl Monkeys {
 
         Self {
             m: monkeys,
-            count: iter::repeat(0).take(n_monkeys).collect(),
+            count: std::iter::repeat_n(0, n_monkeys).collect(),
             prime_product,
         }
     }
@@ -140,10 +140,10 @@ impl Monkeys {
 
                 new_item /= 3;
 
-                let target_monkey_id = if new_item % self.m[i].test_divisor == 0 {
-                    self.m[i].test_true_monkey
+                let target_monkey_id = if new_item.is_multiple_of(self.m[i].test_divisor) {
+                    self.m[i].test_true_target
                 } else {
-                    self.m[i].test_false_monkey
+                    self.m[i].test_false_target
                 };
 
                 self.m[target_monkey_id].items.push_back(new_item);
@@ -160,10 +160,10 @@ impl Monkeys {
 
                 new_item %= self.prime_product;
 
-                let target_monkey_id = if new_item % self.m[i].test_divisor == 0 {
-                    self.m[i].test_true_monkey
+                let target_monkey_id = if new_item.is_multiple_of(self.m[i].test_divisor) {
+                    self.m[i].test_true_target
                 } else {
-                    self.m[i].test_false_monkey
+                    self.m[i].test_false_target
                 };
 
                 self.m[target_monkey_id].items.push_back(new_item);
@@ -206,13 +206,13 @@ mod tests {
                 "example/day11.txt",
                 10_000,
                 WorryManagement::ModProductPrimes,
-                2713310158,
+                2_713_310_158,
             ),
             (
                 "input/day11.txt",
                 10_000,
                 WorryManagement::ModProductPrimes,
-                15048718170,
+                15_048_718_170,
             ),
         ];
 
@@ -1,12 +1,12 @@
-use std::{cmp, cmp::Ordering, fs};
+use std::{cmp::Ordering, fs, slice};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Packet {
+#[derive(Debug, Clone)]
+pub enum Packet {
     List(Vec<Packet>),
     Number(u32),
 }
 
-impl Packet {
+impl From<&str> for Packet {
     fn from(line: &str) -> Self {
         let mut tokens = tokenize(line);
         let mut root = Packet::List(Vec::new());
@@ -17,72 +17,18 @@ impl Packet {
 
         root
     }
-
-    fn partial_cmp_recursive(&self, other: &Self, exit: &mut bool, order: &mut Ordering) {
-        if *exit {
-            return;
-        }
-        match (self, other) {
-            (Packet::List(left), Packet::List(right)) => {
-                let max_length = cmp::max(left.len(), right.len());
-                for i in 0..max_length {
-                    if *exit {
-                        return;
-                    }
-
-                    let left = left.get(i);
-                    let right = right.get(i);
-
-                    match (left, right) {
-                        (Some(left), Some(right)) => left.partial_cmp_recursive(right, exit, order),
-                        (Some(_left), None) => {
-                            // right ran out of items first
-                            *order = Ordering::Greater;
-                            *exit = true;
-                            break;
-                        }
-                        (None, Some(_right)) => {
-                            // left ran out of items first
-                            *order = Ordering::Less;
-                            *exit = true;
-                            break;
-                        }
-                        (None, None) => panic!("exceeded both left and right vec lengths"),
-                    }
-                }
-            }
-
-            (Packet::Number(left), Packet::Number(right)) => match left.cmp(right) {
-                Ordering::Less => {
-                    *order = Ordering::Less;
-                    *exit = true;
-                }
-                Ordering::Greater => {
-                    *order = Ordering::Greater;
-                    *exit = true;
-                }
-                Ordering::Equal => {}
-            },
-
-            (Packet::List(_), Packet::Number(_)) => {
-                let other = &Packet::List(vec![other.clone()]);
-                self.partial_cmp_recursive(other, exit, order);
-            }
-
-            (Packet::Number(_), Packet::List(_)) => {
-                let left = &Packet::List(vec![self.clone()]);
-                left.partial_cmp_recursive(other, exit, order);
-            }
-        }
-    }
 }
 
 impl Ord for Packet {
     fn cmp(&self, other: &Self) -> Ordering {
-        let mut exit = false;
-        let mut order = Ordering::Equal;
-        self.partial_cmp_recursive(other, &mut exit, &mut order);
-        order
+        match (self, other) {
+            (Packet::Number(left), Packet::Number(right)) => left.cmp(right),
+            // lists compare element by element, and the list that runs out first is smaller
+            (Packet::List(left), Packet::List(right)) => left.cmp(right),
+            // a lone number is compared as if it were a list containing only that number
+            (Packet::Number(_), Packet::List(right)) => slice::from_ref(self).cmp(right),
+            (Packet::List(left), Packet::Number(_)) => left.as_slice().cmp(slice::from_ref(other)),
+        }
     }
 }
 
@@ -92,6 +38,15 @@ impl PartialOrd for Packet {
     }
 }
 
+// equality has to agree with the ordering, so `[[2]]` and `2` are equal packets
+impl PartialEq for Packet {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Packet {}
+
 fn parse(tokens: &mut Vec<String>, list: &mut Packet) {
     if let Packet::List(list) = list {
         while let Some(next) = tokens.pop() {
@@ -164,31 +119,57 @@ pub fn sum_ordered_pairs(filename: &str) -> usize {
 }
 
 pub fn sort_all_packets(filename: &str) -> usize {
-    let divider_start = Packet::from("[[2]]");
-    let divider_end = Packet::from("[[6]]");
-    let packets = fs::read_to_string(filename).unwrap();
-    let mut packets: Vec<Packet> = packets
+    let mut packets = parse_packets(filename);
+    let dividers = [Packet::from("[[2]]"), Packet::from("[[6]]")];
+
+    decoder_key(&mut packets, &dividers)
+}
+
+pub fn parse_packets(filename: &str) -> Vec<Packet> {
+    fs::read_to_string(filename)
+        .unwrap()
         .lines()
         .filter(|line| !line.is_empty())
         .map(Packet::from)
-        .collect();
+        .collect()
+}
 
-    packets.push(divider_start.clone());
-    packets.push(divider_end.clone());
+pub fn sort_packets(packets: &mut [Packet]) {
+    // stable, so packets that compare equal keep their relative order
     packets.sort();
+}
 
-    let mut packets = packets.iter();
+// adds the dividers to the packets, sorts everything and returns the 1-based index of each divider,
+// in the same order as the dividers were given
+pub fn insert_dividers(packets: &mut Vec<Packet>, dividers: &[Packet]) -> Vec<usize> {
+    let first_divider = packets.len();
+    packets.extend_from_slice(dividers);
 
-    let divider_start = packets.position(|packet| packet == &divider_start).unwrap() + 1;
-    let divider_end =
-        packets.position(|packet| packet == &divider_end).unwrap() + divider_start + 1;
+    // sort positions rather than packets, so that a divider can be told apart from an equal packet
+    let mut order: Vec<usize> = (0..packets.len()).collect();
+    order.sort_by(|&a, &b| packets[a].cmp(&packets[b]));
 
-    divider_start * divider_end
+    let mut divider_indices = vec![0; dividers.len()];
+    for (index, &position) in order.iter().enumerate() {
+        if position >= first_divider {
+            divider_indices[position - first_divider] = index + 1;
+        }
+    }
+
+    *packets = order.into_iter().map(|i| packets[i].clone()).collect();
+
+    divider_indices
+}
+
+pub fn decoder_key(packets: &mut Vec<Packet>, dividers: &[Packet]) -> usize {
+    insert_dividers(packets, dividers).into_iter().product()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day13;
+    use std::cmp::Ordering;
+
+    use crate::day13::{self, Packet};
     use crate::fetch_input;
 
     #[test]
@@ -216,4 +197,44 @@ mod tests {
             assert_eq!(got, want, "got {got}, wanted {want}, for {filename}");
         }
     }
+
+    #[test]
+    fn insert_dividers() {
+        let mut packets = day13::parse_packets("example/day13.txt");
+        let dividers = [
+            Packet::from("[[2]]"),
+            Packet::from("[[6]]"),
+            Packet::from("[]"),
+        ];
+
+        let got = day13::insert_dividers(&mut packets, &dividers);
+        // the extra `[]` divider sorts after the equal `[]` packet already in the list
+        let want = vec![11, 15, 2];
+
+        assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+        assert_eq!(packets.len(), 19);
+        assert!(packets.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn packet_order() {
+        let tests = vec![
+            ("[1,1,3,1,1]", "[1,1,5,1,1]", Ordering::Less),
+            ("[[1],[2,3,4]]", "[[1],4]", Ordering::Less),
+            ("[9]", "[[8,7,6]]", Ordering::Greater),
+            ("[[4,4],4,4]", "[[4,4],4,4,4]", Ordering::Less),
+            ("[[[]]]", "[[]]", Ordering::Greater),
+            ("[[2]]", "[2]", Ordering::Equal),
+        ];
+
+        for test in tests {
+            let (left, right, want) = test;
+            let got = Packet::from(left).cmp(&Packet::from(right));
+
+            assert_eq!(
+                got, want,
+                "got {got:?}, wanted {want:?}, for {left} and {right}"
+            );
+        }
+    }
 }
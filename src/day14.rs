@@ -1,85 +1,282 @@
-use std::{collections::HashSet, fs};
+use std::{collections::HashSet, fmt, fs};
+
+pub const SAND_SOURCE: Point = Point { x: 500, y: 0 };
+const FLOOR_DEPTH: isize = 2;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point {
-    x: usize,
-    y: usize,
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
 }
 
 impl Point {
-    fn from(x: usize, y: usize) -> Self {
+    pub fn from(x: isize, y: isize) -> Self {
         Self { x, y }
     }
 }
 
-pub fn count_sand(file: &str) -> usize {
-    let rocks = get_rocks(file);
-    let mut sands: HashSet<Point> = HashSet::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Air,
+    Rock,
+    Sand,
+    Source,
+}
 
-    let lowest_rock_height = rocks.iter().map(|point| point.y).max().unwrap();
+pub struct SandSimulation {
+    rocks: HashSet<Point>,
+    sands: HashSet<Point>,
+    sources: Vec<Point>,
+    floor_height: Option<isize>,
+    lowest_rock_height: isize,
+    next_source: usize,
+    finished: bool,
+}
 
-    while let Some(sand) = fall(&rocks, &sands, lowest_rock_height) {
-        sands.insert(sand);
+impl SandSimulation {
+    pub fn from_file(file: &str) -> Self {
+        let rocks = get_rocks(file);
+        let lowest_rock_height = rocks.iter().map(|point| point.y).max().unwrap();
+
+        Self {
+            rocks,
+            sands: HashSet::new(),
+            sources: vec![SAND_SOURCE],
+            floor_height: None,
+            lowest_rock_height,
+            next_source: 0,
+            finished: false,
+        }
     }
 
-    sands.len()
-}
+    // sources take turns dropping a grain each, in the order given
+    #[must_use]
+    pub fn with_sources(mut self, sources: Vec<Point>) -> Self {
+        assert!(!sources.is_empty(), "at least one sand source is needed");
+        self.sources = sources;
+        self.next_source = 0;
+        self
+    }
 
-pub fn count_sand_with_floor(file: &str) -> usize {
-    let mut rocks = get_rocks(file);
-    let mut sands: HashSet<Point> = HashSet::new();
-
-    // add floor
-    let lowest_rock_height = rocks.iter().map(|point| point.y).max().unwrap();
-    let max_x = rocks.iter().map(|point| point.x).max().unwrap();
-    let floor_height = lowest_rock_height + 2;
-    let floor_rocks = (0..=(max_x * floor_height)).map(|x| Point::from(x, lowest_rock_height + 2));
-    rocks.extend(floor_rocks);
-
-    while let Some(sand) = fall(&rocks, &sands, floor_height) {
-        sands.insert(sand);
-        if sand == Point::from(500, 0) {
-            break;
-        }
+    // an infinitely wide floor, `depth` rows below the lowest rock
+    #[must_use]
+    pub fn with_floor(mut self, depth: isize) -> Self {
+        self.floor_height = Some(self.lowest_rock_height + depth);
+        self
     }
 
-    sands.len()
-}
+    pub fn sands(&self) -> &HashSet<Point> {
+        &self.sands
+    }
 
-fn fall(
-    rocks: &HashSet<Point>,
-    sands: &HashSet<Point>,
-    lowest_rock_height: usize,
-) -> Option<Point> {
-    let mut sand = Point::from(500, 0);
+    pub fn resting_sand(&self) -> usize {
+        self.sands.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
 
-    loop {
-        if sand.y > lowest_rock_height {
+    // drops a single grain of sand, returning where it came to rest. `None` means the simulation
+    // is over, either because sand is flowing into the abyss or because every source is blocked
+    pub fn step(&mut self) -> Option<Point> {
+        if self.finished {
             return None;
         }
 
-        let below = Point::from(sand.x, sand.y + 1);
-        let below_left = Point::from(sand.x - 1, sand.y + 1);
-        let below_right = Point::from(sand.x + 1, sand.y + 1);
-
-        // try go down
-        if rocks.contains(&below) || sands.contains(&below) {
-            // try go left
-            if rocks.contains(&below_left) || sands.contains(&below_left) {
-                // try go right
-                if rocks.contains(&below_right) || sands.contains(&below_right) {
-                    // can't go anywhere
-                    return Some(sand);
-                }
+        for _ in 0..self.sources.len() {
+            let source = self.sources[self.next_source];
+            self.next_source = (self.next_source + 1) % self.sources.len();
 
-                sand = below_right;
-            } else {
-                sand = below_left;
+            if self.is_blocked(source) {
+                continue;
+            }
+
+            if let Some(sand) = self.fall(source) {
+                self.sands.insert(sand);
+                return Some(sand);
+            }
+
+            break;
+        }
+
+        self.finished = true;
+        None
+    }
+
+    // runs until the simulation is over, and returns how much sand is at rest
+    pub fn run(&mut self) -> usize {
+        while self.step().is_some() {}
+        self.resting_sand()
+    }
+
+    fn is_blocked(&self, point: Point) -> bool {
+        self.floor_height == Some(point.y)
+            || self.rocks.contains(&point)
+            || self.sands.contains(&point)
+    }
+
+    fn fall(&self, source: Point) -> Option<Point> {
+        let mut sand = source;
+
+        loop {
+            if self.floor_height.is_none() && sand.y > self.lowest_rock_height {
+                return None;
             }
+
+            let below = Point::from(sand.x, sand.y + 1);
+            let below_left = Point::from(sand.x - 1, sand.y + 1);
+            let below_right = Point::from(sand.x + 1, sand.y + 1);
+
+            // try go down, then left, then right
+            match [below, below_left, below_right]
+                .into_iter()
+                .find(|&point| !self.is_blocked(point))
+            {
+                Some(next) => sand = next,
+                // can't go anywhere
+                None => return Some(sand),
+            }
+        }
+    }
+
+    fn cell(&self, point: Point) -> Cell {
+        if self.sands.contains(&point) {
+            Cell::Sand
+        } else if self.sources.contains(&point) {
+            Cell::Source
+        } else if self.floor_height == Some(point.y) || self.rocks.contains(&point) {
+            Cell::Rock
         } else {
-            sand = below;
+            Cell::Air
+        }
+    }
+
+    // top left and bottom right corners of the area worth drawing
+    fn bounds(&self) -> (Point, Point) {
+        let points = || self.rocks.iter().chain(&self.sands).chain(&self.sources);
+
+        let min_x = points().map(|point| point.x).min().unwrap();
+        let max_x = points().map(|point| point.x).max().unwrap();
+        let min_y = points().map(|point| point.y).min().unwrap();
+        let max_y = points().map(|point| point.y).max().unwrap();
+        let max_y = self.floor_height.map_or(max_y, |floor| floor.max(max_y));
+
+        (Point::from(min_x, min_y), Point::from(max_x, max_y))
+    }
+
+    // a binary PPM image of the current frame, one pixel per cell
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let (top_left, bottom_right) = self.bounds();
+        let width = bottom_right.x - top_left.x + 1;
+        let height = bottom_right.y - top_left.y + 1;
+
+        let mut image = format!("P6\n{width} {height}\n255\n").into_bytes();
+
+        for y in top_left.y..=bottom_right.y {
+            for x in top_left.x..=bottom_right.x {
+                let colour = match self.cell(Point::from(x, y)) {
+                    Cell::Air => [0, 0, 0],
+                    Cell::Rock => [128, 128, 128],
+                    Cell::Sand => [194, 178, 128],
+                    Cell::Source => [255, 0, 0],
+                };
+                image.extend(colour);
+            }
+        }
+
+        image
+    }
+}
+
+impl Iterator for SandSimulation {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step()
+    }
+}
+
+impl fmt::Display for SandSimulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (top_left, bottom_right) = self.bounds();
+
+        for y in top_left.y..=bottom_right.y {
+            for x in top_left.x..=bottom_right.x {
+                match self.cell(Point::from(x, y)) {
+                    Cell::Air => write!(f, ".")?,
+                    Cell::Rock => write!(f, "#")?,
+                    Cell::Sand => write!(f, "o")?,
+                    Cell::Source => write!(f, "+")?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn count_sand(file: &str) -> usize {
+    SandSimulation::from_file(file).run()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloorSolver {
+    // drops every grain one at a time
+    Simulation,
+    // counts every cell sand can reach, row by row
+    FloodFill,
+}
+
+pub fn count_sand_with_floor(file: &str) -> usize {
+    count_sand_with_floor_using(file, FloorSolver::FloodFill)
+}
+
+pub fn count_sand_with_floor_using(file: &str, solver: FloorSolver) -> usize {
+    match solver {
+        FloorSolver::Simulation => SandSimulation::from_file(file)
+            .with_floor(FLOOR_DEPTH)
+            .run(),
+        FloorSolver::FloodFill => {
+            let rocks = get_rocks(file);
+            let lowest_rock_height = rocks.iter().map(|point| point.y).max().unwrap();
+            fill_sand(&rocks, SAND_SOURCE, lowest_rock_height + FLOOR_DEPTH)
+        }
+    }
+}
+
+// With a floor, sand keeps falling until the source is blocked, and by then every cell that sand
+// could reach holds a grain. Those cells form a triangle under the source, minus the rocks and the
+// cells shadowed by rocks, which is found one row at a time: a cell can be reached if it is not a
+// rock and one of the three cells above it can be reached.
+fn fill_sand(rocks: &HashSet<Point>, source: Point, floor_height: isize) -> usize {
+    // the triangle is never wider than this, and cells are indexed relative to its left edge
+    let depth = usize::try_from(floor_height - source.y).unwrap();
+    let width = 2 * depth + 1;
+    let left = source.x - isize::try_from(depth).unwrap();
+    let is_rock = |index: usize, y: isize| {
+        rocks.contains(&Point::from(left + isize::try_from(index).unwrap(), y))
+    };
+
+    let mut row = vec![false; width];
+    row[depth] = true;
+    let mut count = 1;
+
+    for y in (source.y + 1)..floor_height {
+        let mut next_row = vec![false; width];
+
+        for (index, reachable) in next_row.iter_mut().enumerate() {
+            let from_above =
+                (index.saturating_sub(1)..=(index + 1).min(width - 1)).any(|above| row[above]);
+            *reachable = from_above && !is_rock(index, y);
         }
+
+        count += next_row.iter().filter(|&&reachable| reachable).count();
+        row = next_row;
     }
+
+    count
 }
 
 fn get_rocks(file: &str) -> HashSet<Point> {
@@ -93,7 +290,7 @@ fn get_rocks(file: &str) -> HashSet<Point> {
             .split("->")
             .map(|point| {
                 let (x, y) = point.trim().split_once(',').unwrap();
-                let (x, y): (usize, usize) = (x.parse().unwrap(), y.parse().unwrap());
+                let (x, y): (isize, isize) = (x.parse().unwrap(), y.parse().unwrap());
                 Point::from(x, y)
             })
             .collect::<Vec<Point>>();
@@ -123,6 +320,7 @@ fn get_rocks(file: &str) -> HashSet<Point> {
 
 #[cfg(test)]
 mod tests {
+    use crate::day14::{FloorSolver, Point, SandSimulation};
     use crate::{day14, fetch_input};
 
     #[test]
@@ -134,7 +332,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day14::count_sand(file);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 
@@ -147,7 +345,83 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day14::count_sand_with_floor(file);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
+        }
+    }
+
+    #[test]
+    fn floor_solvers_agree() {
+        fetch_input(14);
+
+        for file in ["example/day14.txt", "input/day14.txt"] {
+            let simulated = day14::count_sand_with_floor_using(file, FloorSolver::Simulation);
+            let filled = day14::count_sand_with_floor_using(file, FloorSolver::FloodFill);
+            assert_eq!(
+                simulated, filled,
+                "simulation got {simulated}, flood fill got {filled}, for {file}"
+            );
         }
+
+        // a source closer to x = 0 than the floor is deep spreads past it
+        let file = "example/day14.txt";
+        let source = Point::from(3, 0);
+        let simulated = SandSimulation::from_file(file)
+            .with_sources(vec![source])
+            .with_floor(2)
+            .run();
+        let filled = day14::fill_sand(&day14::get_rocks(file), source, 9 + 2);
+        assert_eq!(
+            simulated, filled,
+            "simulation got {simulated}, flood fill got {filled}"
+        );
+    }
+
+    #[test]
+    fn sand_simulation_frames() {
+        let mut simulation = SandSimulation::from_file("example/day14.txt");
+        simulation.by_ref().take(5).for_each(drop);
+
+        let want = "\
+......+...
+..........
+..........
+..........
+....#...##
+....#...#.
+..###...#.
+......o.#.
+....oooo#.
+#########.
+";
+        let got = simulation.to_string();
+        assert_eq!(got, want, "got\n{got}\nwanted\n{want}");
+
+        let image = simulation.to_ppm();
+        assert!(image.starts_with(b"P6\n10 10\n255\n"));
+        assert_eq!(image.len(), b"P6\n10 10\n255\n".len() + 10 * 10 * 3);
+    }
+
+    #[test]
+    fn sand_simulation_with_sources() {
+        let sources = vec![Point::from(500, 0), Point::from(497, 0)];
+        let mut simulation = SandSimulation::from_file("example/day14.txt")
+            .with_sources(sources)
+            .with_floor(2);
+
+        // the first grain from each source lands on a different rock ledge
+        assert_eq!(simulation.next(), Some(Point::from(500, 8)));
+        assert_eq!(simulation.next(), Some(Point::from(497, 5)));
+
+        simulation.run();
+        assert!(simulation.is_finished());
+        assert!(simulation.sands().contains(&Point::from(500, 0)));
+        assert!(simulation.sands().contains(&Point::from(497, 0)));
+
+        // the floor carries on past x = 0, so the pile there is a full triangle
+        let mut simulation = SandSimulation::from_file("example/day14.txt")
+            .with_sources(vec![Point::from(0, 0)])
+            .with_floor(2);
+        assert_eq!(simulation.run(), 11 * 11);
+        assert!(simulation.sands().contains(&Point::from(-10, 10)));
     }
 }
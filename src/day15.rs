@@ -1,33 +1,34 @@
-use std::{collections::HashSet, fs};
+use std::{
+    cmp::{self, Ordering},
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fmt, fs,
+};
 
-const CHARACTERS: [char; 17] = [
-    'S', 'e', 'n', 's', 'o', 'r', 'a', 't', 'c', 'l', 'b', 'i', 'x', 'y', '=', ':', ',',
-];
+pub use crate::interval::{Interval, IntervalSet};
 
 const MULTIPLIER: isize = 4_000_000;
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-struct Point {
-    x: isize,
-    y: isize,
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
 }
 
-#[derive(Clone, Copy)]
-pub struct Interval {
-    start: isize,
-    end: isize,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sensor {
+    pub position: Point,
+    pub beacon: Point,
 }
 
-impl Interval {
-    fn new(start: isize, end: isize) -> Self {
-        Interval { start, end }
+impl Sensor {
+    fn radius(&self) -> isize {
+        isize::try_from(manhattan_distance(&self.position, &self.beacon)).unwrap()
     }
-}
 
-#[derive(Debug)]
-struct Sensor {
-    position: Point,
-    beacon: Point,
+    fn covers(&self, point: &Point) -> bool {
+        isize::try_from(manhattan_distance(&self.position, point)).unwrap() <= self.radius()
+    }
 }
 
 pub fn count_non_beacons(file: &str, row: isize) -> isize {
@@ -44,79 +45,530 @@ pub fn count_non_beacons(file: &str, row: isize) -> isize {
         .try_into()
         .unwrap();
 
-    let points_count: isize = ranges.iter().map(|range| range.end - range.start + 1).sum();
-
-    points_count - beacons_in_row
+    ranges.total_length() - beacons_in_row
 }
 
 pub fn find_distress_beacon(file: &str, interval: Interval) -> usize {
     let sensors = parse_sensors(file);
 
-    for y in interval.start..=interval.end {
-        let ranges = get_non_beacon_ranges(&sensors, y);
+    let frequency = BeaconSearch::new(interval, interval)
+        .tuning_frequency(&sensors)
+        .expect("did not find distress beacon");
+
+    usize::try_from(frequency).unwrap()
+}
+
+pub struct BeaconSearch {
+    x_bounds: Interval,
+    y_bounds: Interval,
+    frequency: Box<dyn Fn(Point) -> isize>,
+}
+
+impl BeaconSearch {
+    // searches the given area, with the puzzle's tuning frequency of x * 4000000 + y
+    pub fn new(x_bounds: Interval, y_bounds: Interval) -> Self {
+        Self {
+            x_bounds,
+            y_bounds,
+            frequency: Box::new(|point| point.x * MULTIPLIER + point.y),
+        }
+    }
+
+    #[must_use]
+    pub fn with_frequency(mut self, frequency: impl Fn(Point) -> isize + 'static) -> Self {
+        self.frequency = Box::new(frequency);
+        self
+    }
+
+    // each row with a spot the sensors miss, and the stretches of x missed there
+    pub fn find_beacons(&self, sensors: &[Sensor]) -> Vec<(isize, IntervalSet)> {
+        find_uncovered_points(sensors, self.x_bounds, self.y_bounds)
+    }
+
+    // uses the uncovered candidate with the smallest y, then x, without filling in the rest of its
+    // region, so a large uncovered area doesn't have to be walked
+    pub fn tuning_frequency(&self, sensors: &[Sensor]) -> Option<isize> {
+        find_uncovered_candidates(sensors, self.x_bounds, self.y_bounds)
+            .into_iter()
+            .min_by_key(|point| (point.y, point.x))
+            .map(|beacon| (self.frequency)(beacon))
+    }
+}
+
+pub fn find_all_uncovered_points(
+    file: &str,
+    x_bounds: Interval,
+    y_bounds: Interval,
+) -> Vec<(isize, IntervalSet)> {
+    let sensors = parse_sensors(file);
+    find_uncovered_points(&sensors, x_bounds, y_bounds)
+}
+
+// In rotated coordinates (u = x + y, v = x - y) every sensor covers a square, bounded by two lines
+// of constant u and two of constant v. Take the uncovered point of a region with the smallest u:
+// the neighbours with smaller u are covered or outside the search area, which puts the point next
+// to where a u-line crosses a v-line, or where a line crosses the edge of the search area. Only
+// those spots are checked. A region's rows run on without a break, so the rows of every region
+// are found by walking up and down from its spots until a row is fully covered, and each row is
+// kept as intervals rather than points.
+fn find_uncovered_points(
+    sensors: &[Sensor],
+    x_bounds: Interval,
+    y_bounds: Interval,
+) -> Vec<(isize, IntervalSet)> {
+    let bounds = IntervalSet::from(x_bounds);
+    let uncovered_in = |y: isize| bounds.difference(&get_non_beacon_ranges(sensors, y));
+
+    let mut rows: BTreeMap<isize, IntervalSet> = BTreeMap::new();
+    for candidate in find_uncovered_candidates(sensors, x_bounds, y_bounds) {
+        if rows.contains_key(&candidate.y) {
+            continue;
+        }
+        rows.insert(candidate.y, uncovered_in(candidate.y));
+
+        for step in [-1, 1] {
+            let mut y = candidate.y + step;
+            while y_bounds.contains(y) && !rows.contains_key(&y) {
+                let uncovered = uncovered_in(y);
+                if uncovered.is_empty() {
+                    break;
+                }
+                rows.insert(y, uncovered);
+                y += step;
+            }
+        }
+    }
+
+    rows.into_iter().collect()
+}
+
+// at least one uncovered point of every uncovered region, from the spots described above
+fn find_uncovered_candidates(
+    sensors: &[Sensor],
+    x_bounds: Interval,
+    y_bounds: Interval,
+) -> Vec<Point> {
+    let in_bounds = |point: &Point| x_bounds.contains(point.x) && y_bounds.contains(point.y);
+    let is_uncovered =
+        |point: &Point| in_bounds(point) && !sensors.iter().any(|sensor| sensor.covers(point));
+
+    let (u_lines, v_lines): (Vec<isize>, Vec<isize>) = sensors
+        .iter()
+        .flat_map(|sensor| {
+            let (x, y, distance) = (sensor.position.x, sensor.position.y, sensor.radius() + 1);
+            [
+                (x + y - distance, x - y - distance),
+                (x + y + distance, x - y + distance),
+            ]
+        })
+        .unzip();
+
+    let mut candidates: Vec<Point> = Vec::new();
+
+    // line crossings, with the lines given as x + y = u and x - y = v, doubled to stay integral
+    for u in &u_lines {
+        for v in &v_lines {
+            candidates.extend(points_near(u + v, u - v));
+        }
+    }
+
+    // lines crossing the edges of the search area
+    for x in [x_bounds.start, x_bounds.end] {
+        for u in &u_lines {
+            candidates.extend(points_near(2 * x, 2 * (u - x)));
+        }
+        for v in &v_lines {
+            candidates.extend(points_near(2 * x, 2 * (x - v)));
+        }
+    }
+    for y in [y_bounds.start, y_bounds.end] {
+        for u in &u_lines {
+            candidates.extend(points_near(2 * (u - y), 2 * y));
+        }
+        for v in &v_lines {
+            candidates.extend(points_near(2 * (v + y), 2 * y));
+        }
+    }
+
+    // corners of the search area
+    for x in [x_bounds.start, x_bounds.end] {
+        for y in [y_bounds.start, y_bounds.end] {
+            candidates.push(Point { x, y });
+        }
+    }
+
+    candidates.into_iter().filter(is_uncovered).collect()
+}
+
+// the integer points within one step of (double_x / 2, double_y / 2), which may fall between cells
+fn points_near(double_x: isize, double_y: isize) -> impl Iterator<Item = Point> {
+    let xs = ((double_x + 1).div_euclid(2) - 1)..=(double_x.div_euclid(2) + 1);
+    let ys = ((double_y + 1).div_euclid(2) - 1)..=(double_y.div_euclid(2) + 1);
+    xs.flat_map(move |x| ys.clone().map(move |y| Point { x, y }))
+}
+
+// a square sensor area, or a union of them, seen in rotated coordinates (u = x + y, v = x - y).
+// only the cells where u and v are both odd or both even are points of the original grid
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotatedRectangle {
+    pub u: Interval,
+    pub v: Interval,
+}
+
+impl RotatedRectangle {
+    pub fn point_count(&self) -> isize {
+        let evens =
+            |interval: &Interval| interval.end.div_euclid(2) - (interval.start - 1).div_euclid(2);
+        let (even_u, even_v) = (evens(&self.u), evens(&self.v));
+        let (odd_u, odd_v) = (self.u.length() - even_u, self.v.length() - even_v);
+
+        even_u * even_v + odd_u * odd_v
+    }
+
+    // the points of the rectangle inside the bounding box. a point at (u, v) is inside when
+    // u + v is in 2 * x_bounds and u - v is in 2 * y_bounds, so for each u the allowed v lie
+    // between the largest of three lines and the smallest of three others. between the places
+    // where those lines cross, the number of points per u grows linearly and is summed directly
+    pub fn points_within(&self, x_bounds: Interval, y_bounds: Interval) -> isize {
+        let u = self.u.intersection(&Interval::new(
+            x_bounds.start + y_bounds.start,
+            x_bounds.end + y_bounds.end,
+        ));
+        if u.is_empty() {
+            return 0;
+        }
+
+        // lines as (v at u = 0, slope)
+        let lower = [
+            (self.v.start, 0),
+            (2 * x_bounds.start, -1),
+            (-2 * y_bounds.end, 1),
+        ];
+        let upper = [
+            (self.v.end, 0),
+            (2 * x_bounds.end, -1),
+            (-2 * y_bounds.start, 1),
+        ];
 
-        let points_count: isize = ranges
+        let mut starts = vec![u.start];
+        for lines in [&lower, &upper] {
+            for (i, &(a, slope_a)) in lines.iter().enumerate() {
+                for &(b, slope_b) in &lines[i + 1..] {
+                    // the first u past the crossing, where the other line takes over
+                    let start = (b - a).div_euclid(slope_a - slope_b) + 1;
+                    if u.start < start && start <= u.end {
+                        starts.push(start);
+                    }
+                }
+            }
+        }
+        starts.sort_unstable();
+        starts.dedup();
+        let ends = starts.iter().skip(1).map(|start| start - 1).chain([u.end]);
+
+        starts
             .iter()
-            // truncate to desired range
-            .map(
-                |range| match (range.start < interval.start, range.end > interval.end) {
-                    (true, true) => Interval::new(interval.start, interval.end),
-                    (true, false) => Interval::new(interval.start, range.end),
-                    (false, true) => Interval::new(range.start, interval.end),
-                    (false, false) => *range,
-                },
-            )
-            .map(|range| range.end - range.start + 1)
-            .sum();
+            .zip(ends)
+            .map(|(&start, end)| {
+                let at = |&(v, slope): &(isize, isize)| v + slope * start;
+                let &low = lower.iter().max_by_key(|line| at(line)).unwrap();
+                let &high = upper.iter().min_by_key(|line| at(line)).unwrap();
+
+                // u and v share their parity, so take every other u and count the v that match it
+                (start..=cmp::min(start + 1, end))
+                    .map(|first| {
+                        let floor = |value: isize| value.div_euclid(2);
+                        let ceil = |value: isize| -(-value).div_euclid(2);
+                        let count = floor(high.0 + high.1 * first - first)
+                            - ceil(low.0 + low.1 * first - first)
+                            + 1;
+                        positive_sum(count, high.1 - low.1, (end - first) / 2 + 1)
+                    })
+                    .sum::<isize>()
+            })
+            .sum()
+    }
+}
 
-        if points_count != interval.end + 1 {
-            // distress beacon in this row!
-            let x = find_excluded_points(&ranges);
-            return usize::try_from(x * MULTIPLIER + y).unwrap();
+// the sum of max(0, first + step * k) for k from 0 up to, but not including, n
+fn positive_sum(first: isize, step: isize, n: isize) -> isize {
+    let (from, to) = match step.cmp(&0) {
+        Ordering::Equal => (0, if first > 0 { n - 1 } else { -1 }),
+        Ordering::Greater => (cmp::max(0, (-first).div_euclid(step) + 1), n - 1),
+        Ordering::Less => (0, cmp::min(n - 1, (first - 1).div_euclid(-step))),
+    };
+    if from > to {
+        return 0;
+    }
+
+    let terms = to - from + 1;
+    terms * first + step * (from + to) * terms / 2
+}
+
+pub struct CoverageMap {
+    sensors: Vec<Sensor>,
+}
+
+impl CoverageMap {
+    pub fn from_file(file: &str) -> Self {
+        Self {
+            sensors: parse_sensors(file),
         }
     }
 
-    panic!("did not find distress beacon")
+    // the union of every sensor area, as disjoint rectangles in rotated coordinates
+    pub fn rectangles(&self) -> Vec<RotatedRectangle> {
+        let squares: Vec<RotatedRectangle> = self
+            .sensors
+            .iter()
+            .map(|sensor| {
+                let (x, y, radius) = (sensor.position.x, sensor.position.y, sensor.radius());
+                RotatedRectangle {
+                    u: Interval::new(x + y - radius, x + y + radius),
+                    v: Interval::new(x - y - radius, x - y + radius),
+                }
+            })
+            .collect();
+
+        // slice the plane wherever a square starts or stops along u
+        let mut slices: Vec<isize> = squares
+            .iter()
+            .flat_map(|square| [square.u.start, square.u.end + 1])
+            .collect();
+        slices.sort_unstable();
+        slices.dedup();
+
+        let mut rectangles: Vec<RotatedRectangle> = Vec::new();
+        let mut open: Vec<RotatedRectangle> = Vec::new();
+
+        for slice in slices.windows(2) {
+            let u = Interval::new(slice[0], slice[1] - 1);
+            let covered: IntervalSet = squares
+                .iter()
+                .filter(|square| square.u.contains_interval(&u))
+                .map(|square| square.v)
+                .collect();
+
+            // rectangles from the previous slice carry on if they cover the same stretch of v
+            let mut still_open = Vec::new();
+            for &v in covered.intervals() {
+                match open.iter().position(|rectangle| rectangle.v == v) {
+                    Some(index) => {
+                        let mut rectangle = open.swap_remove(index);
+                        rectangle.u.end = u.end;
+                        still_open.push(rectangle);
+                    }
+                    None => still_open.push(RotatedRectangle { u, v }),
+                }
+            }
+
+            rectangles.append(&mut open);
+            open = still_open;
+        }
+        rectangles.append(&mut open);
+
+        rectangles
+    }
+
+    pub fn covered_area(&self, x_bounds: Interval, y_bounds: Interval) -> isize {
+        self.rectangles()
+            .iter()
+            .map(|rectangle| rectangle.points_within(x_bounds, y_bounds))
+            .sum()
+    }
+
+    // one character per block of the bounding box, squeezed into at most width by height blocks.
+    // blocks holding a sensor or beacon show it, the rest show whether their middle is covered
+    pub fn render_ascii(
+        &self,
+        x_bounds: Interval,
+        y_bounds: Interval,
+        width: usize,
+        height: usize,
+    ) -> String {
+        let mut image = String::new();
+
+        for row in self.blocks(x_bounds, y_bounds, width, height) {
+            for block in row {
+                image.push(match block {
+                    Block::Sensor => 'S',
+                    Block::Beacon => 'B',
+                    Block::Covered => '#',
+                    Block::Uncovered => '.',
+                });
+            }
+            image.push('\n');
+        }
+
+        image
+    }
+
+    // the same picture as a binary PGM image, one pixel per block
+    pub fn render_pgm(
+        &self,
+        x_bounds: Interval,
+        y_bounds: Interval,
+        width: usize,
+        height: usize,
+    ) -> Vec<u8> {
+        let blocks = self.blocks(x_bounds, y_bounds, width, height);
+        let (width, height) = (blocks[0].len(), blocks.len());
+
+        let mut image = format!("P5\n{width} {height}\n255\n").into_bytes();
+        image.extend(blocks.iter().flatten().map(|block| match block {
+            Block::Sensor => 255,
+            Block::Beacon => 192,
+            Block::Covered => 96,
+            Block::Uncovered => 0,
+        }));
+
+        image
+    }
+
+    fn blocks(
+        &self,
+        x_bounds: Interval,
+        y_bounds: Interval,
+        width: usize,
+        height: usize,
+    ) -> Vec<Vec<Block>> {
+        let x_blocks = split_into_blocks(x_bounds, width);
+        let y_blocks = split_into_blocks(y_bounds, height);
+
+        y_blocks
+            .iter()
+            .map(|y_block| {
+                x_blocks
+                    .iter()
+                    .map(|x_block| {
+                        let in_block =
+                            |point: &Point| x_block.contains(point.x) && y_block.contains(point.y);
+                        let middle = Point {
+                            x: (x_block.start + x_block.end).div_euclid(2),
+                            y: (y_block.start + y_block.end).div_euclid(2),
+                        };
+
+                        if self.sensors.iter().any(|sensor| in_block(&sensor.position)) {
+                            Block::Sensor
+                        } else if self.sensors.iter().any(|sensor| in_block(&sensor.beacon)) {
+                            Block::Beacon
+                        } else if self.sensors.iter().any(|sensor| sensor.covers(&middle)) {
+                            Block::Covered
+                        } else {
+                            Block::Uncovered
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Block {
+    Sensor,
+    Beacon,
+    Covered,
+    Uncovered,
 }
 
-fn find_excluded_points(ranges: &[Interval]) -> isize {
-    assert_eq!(ranges.len(), 2, "should have two large intervals");
-    assert_eq!(
-        ranges[0].end + 1,
-        ranges[1].start - 1,
-        "intervals should have single gap"
-    );
+// splits the interval into at most `count` blocks of (nearly) equal length
+fn split_into_blocks(interval: Interval, count: usize) -> Vec<Interval> {
+    let length = interval.length();
+    let count = isize::try_from(count).unwrap().clamp(1, length.max(1));
 
-    ranges[0].end + 1
+    (0..count)
+        .map(|block| {
+            Interval::new(
+                interval.start + block * length / count,
+                interval.start + (block + 1) * length / count - 1,
+            )
+        })
+        .collect()
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSensorError {
+    pub line_number: usize,
+    pub line: String,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseSensorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} in {:?}",
+            self.line_number, self.reason, self.line
+        )
+    }
+}
+
+impl Error for ParseSensorError {}
+
 fn parse_sensors(file: &str) -> Vec<Sensor> {
     let input = fs::read_to_string(file).unwrap();
+    parse_sensor_log(&input).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Reads one sensor per line. The sensor comes before the first ':' and its beacon after it, and
+/// each side needs an `x=` and a `y=` value. The rest of the line is ignored, so the wording and
+/// the order of the coordinates can change. Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns the first line that can't be read as a sensor.
+pub fn parse_sensor_log(input: &str) -> Result<Vec<Sensor>, ParseSensorError> {
     input
         .lines()
-        .map(|line| {
-            let numbers = line
-                .replace(CHARACTERS, "")
-                .split_whitespace()
-                .map(|n| n.parse().unwrap())
-                .collect::<Vec<isize>>();
-
-            Sensor {
-                position: Point {
-                    x: numbers[0],
-                    y: numbers[1],
-                },
-                beacon: Point {
-                    x: numbers[2],
-                    y: numbers[3],
-                },
-            }
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            parse_sensor(line).map_err(|reason| ParseSensorError {
+                line_number: index + 1,
+                line: line.to_string(),
+                reason,
+            })
         })
         .collect()
 }
 
-fn get_non_beacon_ranges(sensors: &[Sensor], row: isize) -> Vec<Interval> {
-    let mut ranges: Vec<Interval> = Vec::new();
+fn parse_sensor(line: &str) -> Result<Sensor, String> {
+    let (sensor, beacon) = line
+        .split_once(':')
+        .ok_or("missing ':' between sensor and beacon")?;
+
+    Ok(Sensor {
+        position: parse_point(sensor).map_err(|reason| format!("sensor {reason}"))?,
+        beacon: parse_point(beacon).map_err(|reason| format!("beacon {reason}"))?,
+    })
+}
+
+fn parse_point(text: &str) -> Result<Point, String> {
+    Ok(Point {
+        x: parse_coordinate(text, "x=")?,
+        y: parse_coordinate(text, "y=")?,
+    })
+}
+
+fn parse_coordinate(text: &str, key: &str) -> Result<isize, String> {
+    let (_, value) = text
+        .split_once(key)
+        .ok_or_else(|| format!("is missing `{key}`"))?;
+
+    let end = value
+        .char_indices()
+        .find(|&(index, chr)| !(chr.is_ascii_digit() || (index == 0 && chr == '-')))
+        .map_or(value.len(), |(index, _)| index);
+
+    value[..end]
+        .parse()
+        .map_err(|_| format!("has an invalid `{key}` value"))
+}
+
+fn get_non_beacon_ranges(sensors: &[Sensor], row: isize) -> IntervalSet {
+    let mut ranges = IntervalSet::new();
 
     for sensor in sensors {
         // does it reach row
@@ -127,30 +579,11 @@ fn get_non_beacon_ranges(sensors: &[Sensor], row: isize) -> Vec<Interval> {
             let vert_distance = isize::try_from(beacon_distance - row_distance).unwrap();
             let row_start = sensor.position.x - vert_distance;
             let row_end = sensor.position.x + vert_distance;
-            ranges.push(Interval::new(row_start, row_end));
+            ranges.insert(Interval::new(row_start, row_end));
         }
     }
 
-    merge_intervals(&mut ranges)
-}
-
-fn merge_intervals(ranges: &mut [Interval]) -> Vec<Interval> {
-    ranges.sort_by_key(|range| range.start);
-
-    let mut result: Vec<Interval> = vec![ranges[0]];
-
-    for range in ranges.iter().skip(1) {
-        let last_range = result.pop().unwrap();
-        if range.start <= last_range.end {
-            let new_end = last_range.end.max(range.end);
-            result.push(Interval::new(last_range.start, new_end));
-        } else {
-            result.push(last_range);
-            result.push(*range);
-        }
-    }
-
-    result
+    ranges
 }
 
 fn manhattan_distance(a: &Point, b: &Point) -> usize {
@@ -159,6 +592,11 @@ fn manhattan_distance(a: &Point, b: &Point) -> usize {
 
 #[cfg(test)]
 mod tests {
+    use crate::day15::{
+        get_non_beacon_ranges, BeaconSearch, CoverageMap, Interval, ParseSensorError, Point,
+        RotatedRectangle, Sensor,
+    };
+    use crate::interval::IntervalSet;
     use crate::{day15, fetch_input};
 
     #[test]
@@ -173,7 +611,7 @@ mod tests {
         for test in tests {
             let (file, want, row) = test;
             let got = day15::count_non_beacons(file, row);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 
@@ -193,7 +631,156 @@ mod tests {
         for test in tests {
             let (file, want, interval) = test;
             let got = day15::find_distress_beacon(file, interval);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
+        }
+    }
+
+    #[test]
+    fn find_all_uncovered_points() {
+        let tests = vec![
+            (Interval::new(0, 20), Interval::new(0, 20)),
+            // gaps running off the edges and into the corners of the search area
+            (Interval::new(-3, 27), Interval::new(-5, 24)),
+            (Interval::new(14, 30), Interval::new(-8, 3)),
+        ];
+
+        let sensors = day15::parse_sensors("example/day15.txt");
+
+        for test in tests {
+            let (x_bounds, y_bounds) = test;
+            let got: Vec<Point> =
+                day15::find_all_uncovered_points("example/day15.txt", x_bounds, y_bounds)
+                    .into_iter()
+                    .flat_map(|(y, xs)| {
+                        xs.intervals()
+                            .iter()
+                            .flat_map(|interval| interval.start..=interval.end)
+                            .map(move |x| Point { x, y })
+                            .collect::<Vec<Point>>()
+                    })
+                    .collect();
+
+            // check every point in the search area
+            let want: Vec<Point> = (y_bounds.start..=y_bounds.end)
+                .flat_map(|y| (x_bounds.start..=x_bounds.end).map(move |x| Point { x, y }))
+                .filter(|point| !sensors.iter().any(|sensor| sensor.covers(point)))
+                .collect();
+
+            assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+        }
+    }
+
+    #[test]
+    fn beacon_search() {
+        let sensors = day15::parse_sensors("example/day15.txt");
+
+        let search = BeaconSearch::new(Interval::new(0, 20), Interval::new(0, 20));
+        assert_eq!(search.tuning_frequency(&sensors), Some(56_000_011));
+
+        // a search area that is not square, with a different frequency
+        let search = BeaconSearch::new(Interval::new(10, 15), Interval::new(8, 12))
+            .with_frequency(|point| point.y * 100 + point.x);
+        let want = vec![(11, IntervalSet::from(Interval::new(14, 14)))];
+        assert_eq!(search.find_beacons(&sensors), want);
+        assert_eq!(search.tuning_frequency(&sensors), Some(1114));
+
+        let search = BeaconSearch::new(Interval::new(0, 10), Interval::new(0, 10));
+        assert_eq!(search.tuning_frequency(&sensors), None);
+
+        // an uncovered area comes back as one interval per row
+        let x_bounds = Interval::new(1000, 2000);
+        let search = BeaconSearch::new(x_bounds, Interval::new(0, 999));
+        let want: Vec<(isize, IntervalSet)> = (0..1000)
+            .map(|y| (y, IntervalSet::from(x_bounds)))
+            .collect();
+        assert_eq!(search.find_beacons(&sensors), want);
+
+        // far away from every sensor, so the whole area is uncovered and never walked
+        let search = BeaconSearch::new(
+            Interval::new(1_000_000, 3_000_000),
+            Interval::new(0, 2_000_000),
+        );
+        assert_eq!(search.tuning_frequency(&sensors), Some(4_000_000_000_000));
+    }
+
+    #[test]
+    fn parse_sensor_log() {
+        let log = "\
+Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+
+sensor y=-3 x=7 : beacon y=4 x=10
+Sensor at x=9, y=16: closest beacon is at x=10
+";
+        let got = day15::parse_sensor_log(log).unwrap_err();
+        let want = ParseSensorError {
+            line_number: 4,
+            line: "Sensor at x=9, y=16: closest beacon is at x=10".to_string(),
+            reason: "beacon is missing `y=`".to_string(),
+        };
+        assert_eq!(got, want, "got {got}, wanted {want}");
+
+        let got = day15::parse_sensor_log(log.rsplit_once("Sensor").unwrap().0).unwrap();
+        let want = vec![
+            Sensor {
+                position: Point { x: 2, y: 18 },
+                beacon: Point { x: -2, y: 15 },
+            },
+            Sensor {
+                position: Point { x: 7, y: -3 },
+                beacon: Point { x: 10, y: 4 },
+            },
+        ];
+        assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+    }
+
+    #[test]
+    fn coverage_map() {
+        let map = CoverageMap::from_file("example/day15.txt");
+
+        // everything but the distress beacon is covered
+        let got = map.covered_area(Interval::new(0, 20), Interval::new(0, 20));
+        assert_eq!(got, 21 * 21 - 1, "got {got}");
+
+        // the rectangles cover exactly the points the rows do
+        let bounds = Interval::new(-20, 40);
+        let got: isize = map
+            .rectangles()
+            .iter()
+            .map(RotatedRectangle::point_count)
+            .sum();
+        let want = map.covered_area(bounds, bounds);
+        assert_eq!(got, want, "got {got}, wanted {want}");
+
+        // clipping the rectangles to a box covers the same points as the rows inside it
+        let tests = vec![
+            (Interval::new(0, 20), Interval::new(0, 20)),
+            (Interval::new(-3, 11), Interval::new(4, 25)),
+            (Interval::new(7, 7), Interval::new(-9, 30)),
+            (Interval::new(14, 40), Interval::new(11, 11)),
+            (Interval::new(100, 120), Interval::new(0, 20)),
+        ];
+        for test in tests {
+            let (x_bounds, y_bounds) = test;
+            let got = map.covered_area(x_bounds, y_bounds);
+            let want: isize = (y_bounds.start..=y_bounds.end)
+                .map(|y| {
+                    get_non_beacon_ranges(&map.sensors, y)
+                        .intersection(&IntervalSet::from(x_bounds))
+                        .total_length()
+                })
+                .sum();
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
+
+        let got = map.render_ascii(Interval::new(8, 16), Interval::new(9, 14), 9, 6);
+        let want = "\
+#########
+#########
+######.##
+#########
+#########
+####S####
+";
+        assert_eq!(got, want, "got\n{got}\nwanted\n{want}");
     }
 }
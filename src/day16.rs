@@ -14,7 +14,11 @@
 ///      - 60 possible positions you could be in
 ///  So although there are a lot of possible routes through the graph, a lot of them will be
 ///  repeated, so we can do DP!
-use std::{collections::HashMap, fs};
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    fs,
+};
 
 const MAX_MINUTES: isize = 30;
 const MAX_MINUTES_WITH_ELEPHANT: isize = 26;
@@ -23,6 +27,7 @@ const MAX_MINUTES_WITH_ELEPHANT: isize = 26;
 struct Volcano {
     flow_rates: HashMap<isize, isize>,
     graph: HashMap<isize, HashMap<isize, isize>>,
+    names: HashMap<isize, String>,
     start_id: isize,
 }
 
@@ -83,6 +88,10 @@ impl Volcano {
             graph,
             flow_rates,
             start_id: *valve_name_to_id.get("AA").unwrap(),
+            names: valve_name_to_id
+                .into_iter()
+                .map(|(name, id)| (id, name))
+                .collect(),
         }
     }
 
@@ -115,6 +124,149 @@ impl Volcano {
         }
         self
     }
+
+    // the valves worth opening, in the order the solvers number them by their bit in a set
+    fn valves_by_bit(&self) -> Vec<isize> {
+        let mut valves: Vec<isize> = self
+            .flow_rates
+            .iter()
+            .filter(|(_, flow_rate)| **flow_rate > 0)
+            .map(|(valve, _)| *valve)
+            .collect();
+        valves.sort_unstable();
+        valves
+    }
+
+    // Floyd-Warshall over whatever valves are left in the graph
+    fn shortest_distances(&self) -> HashMap<(isize, isize), isize> {
+        let mut distances: HashMap<(isize, isize), isize> = HashMap::new();
+        for (valve, neighbours) in &self.graph {
+            distances.insert((*valve, *valve), 0);
+            for (neighbour, distance) in neighbours {
+                let shortest = distances.entry((*valve, *neighbour)).or_insert(*distance);
+                *shortest = (*shortest).min(*distance);
+            }
+        }
+
+        for via in self.graph.keys() {
+            for from in self.graph.keys() {
+                for to in self.graph.keys() {
+                    if let (Some(first), Some(second)) =
+                        (distances.get(&(*from, *via)), distances.get(&(*via, *to)))
+                    {
+                        let through = first + second;
+                        let shortest = distances.entry((*from, *to)).or_insert(through);
+                        *shortest = (*shortest).min(through);
+                    }
+                }
+            }
+        }
+
+        distances
+    }
+}
+
+// the tunnel network with the zero-flow valves squeezed out, apart from the starting valve
+pub struct ValveGraph {
+    volcano: Volcano,
+    distances: HashMap<(isize, isize), isize>,
+}
+
+impl ValveGraph {
+    pub fn from_file(filename: &str) -> Self {
+        let volcano = Volcano::from_file(filename).pruned();
+        let distances = volcano.shortest_distances();
+        Self { volcano, distances }
+    }
+
+    pub fn valves(&self) -> Vec<&str> {
+        let mut valves: Vec<&str> = self
+            .volcano
+            .graph
+            .keys()
+            .map(|valve| self.volcano.names[valve].as_str())
+            .collect();
+        valves.sort_unstable();
+        valves
+    }
+
+    pub fn flow_rate(&self, valve: &str) -> Option<isize> {
+        self.id(valve).map(|valve| self.volcano.flow_rates[&valve])
+    }
+
+    // direct tunnels out of a valve, and how many minutes each takes
+    pub fn tunnels(&self, valve: &str) -> Vec<(&str, isize)> {
+        let Some(valve) = self.id(valve) else {
+            return Vec::new();
+        };
+        let mut tunnels: Vec<(&str, isize)> = self.volcano.graph[&valve]
+            .iter()
+            .map(|(neighbour, distance)| (self.volcano.names[neighbour].as_str(), *distance))
+            .collect();
+        tunnels.sort_unstable();
+        tunnels
+    }
+
+    pub fn distance(&self, from: &str, to: &str) -> Option<isize> {
+        let (from, to) = (self.id(from)?, self.id(to)?);
+        self.distances.get(&(from, to)).copied()
+    }
+
+    // shortest travel time between every pair of valves that are connected
+    pub fn all_distances(&self) -> HashMap<(String, String), isize> {
+        self.distances
+            .iter()
+            .map(|((from, to), distance)| {
+                let names = (
+                    self.volcano.names[from].clone(),
+                    self.volcano.names[to].clone(),
+                );
+                (names, *distance)
+            })
+            .collect()
+    }
+
+    // every other valve that can be reached within the time, closest first
+    pub fn reachable_within(&self, from: &str, minutes: isize) -> Vec<(&str, isize)> {
+        let mut reachable: Vec<(&str, isize)> = self
+            .valves()
+            .into_iter()
+            .filter(|valve| valve != &from)
+            .filter_map(|valve| Some((valve, self.distance(from, valve)?)))
+            .filter(|(_, distance)| *distance <= minutes)
+            .collect();
+        reachable.sort_by_key(|&(valve, distance)| (distance, valve));
+        reachable
+    }
+
+    // Graphviz DOT, with each tunnel drawn once and labelled with its length
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph valves {\n");
+
+        for valve in self.valves() {
+            let flow_rate = self.flow_rate(valve).unwrap();
+            writeln!(dot, "    {valve} [label=\"{valve}\\n{flow_rate}\"];").unwrap();
+        }
+
+        for valve in self.valves() {
+            for (neighbour, distance) in self.tunnels(valve) {
+                if valve < neighbour {
+                    writeln!(dot, "    {valve} -- {neighbour} [label={distance}];").unwrap();
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn id(&self, valve: &str) -> Option<isize> {
+        self.volcano
+            .names
+            .iter()
+            .find(|(id, name)| name.as_str() == valve && self.volcano.graph.contains_key(id))
+            .map(|(id, _)| *id)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -158,7 +310,7 @@ fn find_best_route(
 
     if let Some(answer) = dp.get(&state) {
         return *answer;
-    };
+    }
 
     let mut answer = 0;
     let not_open = opened_valves & (1 << valve) == 0;
@@ -187,27 +339,471 @@ fn find_best_route(
 }
 
 pub fn maximize_pressure_release(filename: &str) -> isize {
+    explain_pressure_release(filename).total_pressure
+}
+
+pub fn maximize_pressure_release_with_elephant(filename: &str) -> isize {
+    explain_pressure_release_with_elephant(filename).total_pressure
+}
+
+pub fn explain_pressure_release(filename: &str) -> Schedule {
     let volcano = Volcano::from_file(filename).pruned();
+    explain_best_route(&volcano, MAX_MINUTES, false)
+}
 
-    let mut dp: HashMap<State, isize> = HashMap::new();
-    find_best_route(volcano.start_id, 0, MAX_MINUTES, false, &volcano, &mut dp)
+pub fn explain_pressure_release_with_elephant(filename: &str) -> Schedule {
+    let volcano = Volcano::from_file(filename).pruned();
+    explain_best_route(&volcano, MAX_MINUTES_WITH_ELEPHANT, true)
 }
 
-pub fn maximize_pressure_release_with_elephant(filename: &str) -> isize {
+// Any number of agents, each given the same number of minutes. Every agent opens its own valves, so
+// the best total comes from splitting the valves into disjoint sets, one per agent, and adding up
+// the best each agent can do with its set.
+pub fn maximize_pressure_release_with_agents(
+    filename: &str,
+    agents: usize,
+    minutes: isize,
+) -> isize {
+    if agents == 0 {
+        return 0;
+    }
+
+    let volcano = Volcano::from_file(filename).pruned();
+    let best = best_pressure_per_valve_set(&volcano, minutes);
+
+    // combined[set] is the best the agents so far can do, opening only valves in the set
+    let mut combined = best.clone();
+    for _ in 1..agents {
+        combined = (0..best.len())
+            .map(|set| {
+                let mut answer = 0;
+                let mut subset = set;
+                loop {
+                    answer = answer.max(combined[subset] + best[set ^ subset]);
+                    if subset == 0 {
+                        break;
+                    }
+                    subset = (subset - 1) & set;
+                }
+                answer
+            })
+            .collect();
+    }
+
+    combined[best.len() - 1]
+}
+
+struct ValveSets<'a> {
+    // the valves worth opening, numbered by their bit in a set
+    valves: Vec<isize>,
+    distances: HashMap<(isize, isize), isize>,
+    volcano: &'a Volcano,
+}
+
+// the best pressure a single agent can release, for every set of non-zero valves it may open
+fn best_pressure_per_valve_set(volcano: &Volcano, minutes: isize) -> Vec<isize> {
+    let valves = volcano.valves_by_bit();
+
+    let valve_sets = ValveSets {
+        distances: volcano.shortest_distances(),
+        valves,
+        volcano,
+    };
+
+    let mut best = vec![0; 1 << valve_sets.valves.len()];
+    valve_sets.explore(volcano.start_id, minutes, 0, 0, &mut best);
+
+    // being allowed more valves never makes things worse
+    for bit in 0..valve_sets.valves.len() {
+        for set in 0..best.len() {
+            if set & (1 << bit) != 0 {
+                best[set] = best[set].max(best[set ^ (1 << bit)]);
+            }
+        }
+    }
+
+    best
+}
+
+impl ValveSets<'_> {
+    fn explore(
+        &self,
+        valve: isize,
+        time: isize,
+        opened: usize,
+        pressure: isize,
+        best: &mut [isize],
+    ) {
+        best[opened] = best[opened].max(pressure);
+
+        for (bit, next_valve) in self.valves.iter().enumerate() {
+            if opened & (1 << bit) != 0 {
+                continue;
+            }
+
+            // walk there and open it
+            let time_left = time - self.distances[&(valve, *next_valve)] - 1;
+            if time_left <= 0 {
+                continue;
+            }
+
+            let released = time_left * self.volcano.flow_rates[next_valve];
+            self.explore(
+                *next_valve,
+                time_left,
+                opened | (1 << bit),
+                pressure + released,
+                best,
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    // caches every state in a flat table indexed by agent, valve, time left and opened valves as a
+    // bitset. falls back to branch and bound if the table would need more than `max_entries`
+    // entries, or if a pressure could be too big to store in one
+    DenseMemo { max_entries: usize },
+    // caches nothing, and drops any route that cannot beat the best one found so far even if every
+    // valve left was reached in a straight line
+    BranchAndBound,
+}
+
+// the agents take turns, each one going all the way round before the next sets off from the start
+pub fn search_pressure_release(
+    filename: &str,
+    agents: usize,
+    minutes: isize,
+    mode: SearchMode,
+) -> isize {
+    if agents == 0 {
+        return 0;
+    }
+
     let volcano = Volcano::from_file(filename).pruned();
+    let mut search = RouteSearch::new(&volcano, agents, minutes);
+
+    match mode {
+        SearchMode::DenseMemo { max_entries }
+            if search.memo_size() <= max_entries && search.fits_memo() =>
+        {
+            search.memo = vec![UNKNOWN; search.memo_size()];
+            search.best_from(0, search.start, minutes, 0)
+        }
+        SearchMode::DenseMemo { .. } | SearchMode::BranchAndBound => {
+            search.branch(0, search.start, minutes, 0, 0);
+            search.best
+        }
+    }
+}
+
+const UNKNOWN: u32 = u32::MAX;
+
+struct RouteSearch {
+    // flow rates of the valves worth opening, numbered by their bit in the opened bitset
+    flow_rates: Vec<isize>,
+    // distances[from][to], where the start is the last `from`
+    distances: Vec<Vec<isize>>,
+    start: usize,
+    agents: usize,
+    minutes: isize,
+    memo: Vec<u32>,
+    best: isize,
+}
+
+impl RouteSearch {
+    fn new(volcano: &Volcano, agents: usize, minutes: isize) -> Self {
+        let valves = volcano.valves_by_bit();
+
+        let all_distances = volcano.shortest_distances();
+        let distances = valves
+            .iter()
+            .chain([&volcano.start_id])
+            .map(|from| {
+                valves
+                    .iter()
+                    .map(|to| all_distances[&(*from, *to)])
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            flow_rates: valves
+                .iter()
+                .map(|valve| volcano.flow_rates[valve])
+                .collect(),
+            distances,
+            start: valves.len(),
+            agents,
+            minutes,
+            memo: Vec::new(),
+            best: 0,
+        }
+    }
+
+    fn memo_size(&self) -> usize {
+        let times = usize::try_from(self.minutes + 1).unwrap();
+        let bits = u32::try_from(self.flow_rates.len()).unwrap_or(u32::MAX);
+        if bits >= usize::BITS {
+            return usize::MAX;
+        }
+        self.agents
+            .saturating_mul(self.start + 1)
+            .saturating_mul(times)
+            .saturating_mul(1 << bits)
+    }
+
+    // every valve open from the first minute bounds any pressure, and it has to stay below UNKNOWN
+    fn fits_memo(&self) -> bool {
+        let pressure = self
+            .flow_rates
+            .iter()
+            .sum::<isize>()
+            .saturating_mul(self.minutes);
+        u32::try_from(pressure).is_ok_and(|pressure| pressure < UNKNOWN)
+    }
+
+    fn memo_index(&self, agent: usize, valve: usize, time: isize, opened: usize) -> usize {
+        let times = usize::try_from(self.minutes + 1).unwrap();
+        let time = usize::try_from(time).unwrap();
+        (((agent * (self.start + 1) + valve) * times + time) << self.flow_rates.len()) | opened
+    }
+
+    // the valves still to open, with the time left once they are reached and opened
+    fn next_valves(&self, valve: usize, time: isize, opened: usize) -> Vec<(usize, isize)> {
+        (0..self.flow_rates.len())
+            .filter(|next_valve| opened & (1 << next_valve) == 0)
+            .map(|next_valve| (next_valve, time - self.distances[valve][next_valve] - 1))
+            .filter(|(_, time_left)| *time_left > 0)
+            .collect()
+    }
+
+    // best pressure still to be released from this state, by the current agent and the ones after it
+    fn best_from(&mut self, agent: usize, valve: usize, time: isize, opened: usize) -> isize {
+        let index = self.memo_index(agent, valve, time, opened);
+        if self.memo[index] != UNKNOWN {
+            return isize::try_from(self.memo[index]).unwrap();
+        }
+
+        let mut answer = if agent + 1 < self.agents {
+            self.best_from(agent + 1, self.start, self.minutes, opened)
+        } else {
+            0
+        };
+
+        for (next_valve, time_left) in self.next_valves(valve, time, opened) {
+            answer = answer.max(
+                time_left * self.flow_rates[next_valve]
+                    + self.best_from(agent, next_valve, time_left, opened | (1 << next_valve)),
+            );
+        }
+
+        self.memo[index] = u32::try_from(answer).unwrap();
+        answer
+    }
+
+    // every valve still closed, opened as early as any agent could possibly get to it
+    fn optimistic_pressure(&self, agent: usize, valve: usize, time: isize, opened: usize) -> isize {
+        (0..self.flow_rates.len())
+            .filter(|next_valve| opened & (1 << next_valve) == 0)
+            .map(|next_valve| {
+                let mut time_left = time - self.distances[valve][next_valve] - 1;
+                if agent + 1 < self.agents {
+                    time_left =
+                        time_left.max(self.minutes - self.distances[self.start][next_valve] - 1);
+                }
+                time_left.max(0) * self.flow_rates[next_valve]
+            })
+            .sum()
+    }
+
+    fn branch(&mut self, agent: usize, valve: usize, time: isize, opened: usize, pressure: isize) {
+        self.best = self.best.max(pressure);
+        if pressure + self.optimistic_pressure(agent, valve, time, opened) <= self.best {
+            return;
+        }
+
+        // the most promising valves first, so that good routes are found early
+        let mut next_valves = self.next_valves(valve, time, opened);
+        next_valves
+            .sort_by_key(|&(next_valve, time_left)| -(time_left * self.flow_rates[next_valve]));
+
+        for (next_valve, time_left) in next_valves {
+            let released = time_left * self.flow_rates[next_valve];
+            self.branch(
+                agent,
+                next_valve,
+                time_left,
+                opened | (1 << next_valve),
+                pressure + released,
+            );
+        }
+
+        if agent + 1 < self.agents {
+            self.branch(agent + 1, self.start, self.minutes, opened, pressure);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Travel {
+        from: String,
+        to: String,
+        minutes: isize,
+    },
+    Open {
+        valve: String,
+        flow_rate: isize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    // the minute the action starts in, counting from 1
+    pub minute: isize,
+    // 0 is you, 1 is the elephant
+    pub agent: usize,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub steps: Vec<Step>,
+    // pressure released during each minute, starting from minute 1
+    pub pressure_per_minute: Vec<isize>,
+    pub total_pressure: isize,
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            let agent = if step.agent == 0 {
+                "you"
+            } else {
+                "the elephant"
+            };
+            match &step.action {
+                Action::Travel { from, to, minutes } => writeln!(
+                    f,
+                    "minute {}, {agent}: move from {from} to {to} ({minutes} min)",
+                    step.minute
+                )?,
+                Action::Open { valve, flow_rate } => writeln!(
+                    f,
+                    "minute {}, {agent}: open {valve} ({flow_rate} pressure per minute)",
+                    step.minute
+                )?,
+            }
+        }
+        writeln!(f, "total pressure released: {}", self.total_pressure)
+    }
+}
+
+// runs the DP, then walks back through the cached results to find which choices gave the answer
+fn explain_best_route(volcano: &Volcano, max_minutes: isize, with_elephant: bool) -> Schedule {
     let mut dp: HashMap<State, isize> = HashMap::new();
-    find_best_route(
+    let total_pressure = find_best_route(
         volcano.start_id,
         0,
-        MAX_MINUTES_WITH_ELEPHANT,
-        true,
-        &volcano,
+        max_minutes,
+        with_elephant,
+        volcano,
         &mut dp,
-    )
+    );
+
+    let mut steps = Vec::new();
+    let mut opened_at: Vec<(isize, isize)> = Vec::new();
+    let mut opened_valves = 0;
+
+    let agents = if with_elephant {
+        [true, false].as_slice()
+    } else {
+        &[false]
+    };
+
+    for (agent, &with_elephant) in agents.iter().enumerate() {
+        let mut valve = volcano.start_id;
+        let mut time = max_minutes;
+
+        loop {
+            let mut best = |valve, opened_valves, time| {
+                find_best_route(valve, opened_valves, time, with_elephant, volcano, &mut dp)
+            };
+
+            let answer = best(valve, opened_valves, time);
+            // nothing left for this agent to gain by moving, so the rest is up to the next agent
+            if time <= 0 || answer == best(valve, opened_valves, 0) {
+                break;
+            }
+
+            let flow_rate = *volcano.flow_rates.get(&valve).unwrap();
+            let not_open = opened_valves & (1 << valve) == 0;
+            if not_open
+                && flow_rate > 0
+                && answer
+                    == (time - 1) * flow_rate + best(valve, opened_valves | (1 << valve), time - 1)
+            {
+                steps.push(Step {
+                    minute: max_minutes - time + 1,
+                    agent,
+                    action: Action::Open {
+                        valve: volcano.names[&valve].clone(),
+                        flow_rate,
+                    },
+                });
+                opened_at.push((max_minutes - time + 1, flow_rate));
+                opened_valves |= 1 << valve;
+                time -= 1;
+                continue;
+            }
+
+            let (next_valve, distance) = volcano.graph[&valve]
+                .iter()
+                .find(|(next_valve, distance)| {
+                    time - *distance > 0
+                        && answer == best(**next_valve, opened_valves, time - *distance)
+                })
+                .expect("a cached choice gives the answer");
+
+            steps.push(Step {
+                minute: max_minutes - time + 1,
+                agent,
+                action: Action::Travel {
+                    from: volcano.names[&valve].clone(),
+                    to: volcano.names[next_valve].clone(),
+                    minutes: *distance,
+                },
+            });
+            valve = *next_valve;
+            time -= distance;
+        }
+    }
+
+    steps.sort_by_key(|step| (step.minute, step.agent));
+
+    // a valve releases pressure from the minute after it is opened
+    let pressure_per_minute = (1..=max_minutes)
+        .map(|minute| {
+            opened_at
+                .iter()
+                .filter(|(opened, _)| *opened < minute)
+                .map(|(_, flow_rate)| flow_rate)
+                .sum()
+        })
+        .collect();
+
+    Schedule {
+        steps,
+        pressure_per_minute,
+        total_pressure,
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::day16::{Action, SearchMode, ValveGraph};
     use crate::{day16, fetch_input};
 
     #[test]
@@ -219,7 +815,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day16::maximize_pressure_release(file);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 
@@ -232,7 +828,121 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day16::maximize_pressure_release_with_elephant(file);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
+
+    #[test]
+    fn maximize_pressure_release_with_agents() {
+        fetch_input(16);
+
+        let tests = vec![
+            ("example/day16.txt", 1651, 1, 30),
+            ("example/day16.txt", 1707, 2, 26),
+            ("example/day16.txt", 0, 0, 30),
+            ("input/day16.txt", 1789, 1, 30),
+            ("input/day16.txt", 2496, 2, 26),
+        ];
+
+        for test in tests {
+            let (file, want, agents, minutes) = test;
+            let got = day16::maximize_pressure_release_with_agents(file, agents, minutes);
+            assert_eq!(got, want, "got {got}, wanted {want}, for {agents} agents");
+        }
+
+        // more agents can only help
+        let one = day16::maximize_pressure_release_with_agents("example/day16.txt", 1, 22);
+        let three = day16::maximize_pressure_release_with_agents("example/day16.txt", 3, 22);
+        let six = day16::maximize_pressure_release_with_agents("example/day16.txt", 6, 22);
+        assert!(one < three && three <= six, "got {one}, {three} and {six}");
+    }
+
+    #[test]
+    fn search_pressure_release() {
+        fetch_input(16);
+
+        let dense = SearchMode::DenseMemo {
+            max_entries: 1 << 26,
+        };
+        // too small for any table, so it falls back to branch and bound
+        let tiny = SearchMode::DenseMemo { max_entries: 0 };
+        let bound = SearchMode::BranchAndBound;
+
+        let tests = vec![
+            ("example/day16.txt", 1651, 1, 30, vec![dense, tiny, bound]),
+            ("example/day16.txt", 1707, 2, 26, vec![dense, tiny, bound]),
+            ("input/day16.txt", 1789, 1, 30, vec![dense, bound]),
+            ("input/day16.txt", 2496, 2, 26, vec![bound]),
+        ];
+
+        for test in tests {
+            let (file, want, agents, minutes, modes) = test;
+            for mode in modes {
+                let got = day16::search_pressure_release(file, agents, minutes, mode);
+                assert_eq!(got, want, "got {got}, wanted {want}, for {mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn valve_graph() {
+        let graph = ValveGraph::from_file("example/day16.txt");
+
+        assert_eq!(
+            graph.valves(),
+            vec!["AA", "BB", "CC", "DD", "EE", "HH", "JJ"]
+        );
+        assert_eq!(graph.flow_rate("HH"), Some(22));
+        assert_eq!(graph.flow_rate("FF"), None);
+        assert_eq!(graph.distance("AA", "HH"), Some(5));
+        assert_eq!(graph.distance("JJ", "HH"), Some(7));
+        assert_eq!(graph.all_distances().len(), 7 * 7);
+        assert_eq!(
+            graph.reachable_within("AA", 2),
+            vec![("BB", 1), ("DD", 1), ("CC", 2), ("EE", 2), ("JJ", 2)]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("graph valves {\n"));
+        assert!(dot.contains("    AA -- JJ [label=2];\n"));
+        assert!(dot.contains("    EE -- HH [label=3];\n"));
+        assert!(!dot.contains("JJ -- AA"));
+    }
+
+    #[test]
+    fn explain_pressure_release() {
+        let schedules = vec![
+            day16::explain_pressure_release("example/day16.txt"),
+            day16::explain_pressure_release_with_elephant("example/day16.txt"),
+        ];
+
+        for schedule in &schedules {
+            let released: isize = schedule.pressure_per_minute.iter().sum();
+            assert_eq!(released, schedule.total_pressure, "for\n{schedule}");
+        }
+
+        let got: Vec<(isize, String)> = schedules[0]
+            .steps
+            .iter()
+            .filter_map(|step| match &step.action {
+                Action::Open { valve, .. } => Some((step.minute, valve.clone())),
+                Action::Travel { .. } => None,
+            })
+            .collect();
+        let want: Vec<(isize, String)> = [
+            (2, "DD"),
+            (5, "BB"),
+            (9, "JJ"),
+            (17, "HH"),
+            (21, "EE"),
+            (24, "CC"),
+        ]
+        .into_iter()
+        .map(|(minute, valve)| (minute, valve.to_string()))
+        .collect();
+        assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+
+        // the elephant opens some of the valves
+        assert!(schedules[1].steps.iter().any(|step| step.agent == 1));
+    }
 }
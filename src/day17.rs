@@ -1,52 +1,117 @@
-use std::{fmt, fs, iter::Cycle, slice::Iter};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Write},
+};
 
-const TEST_CHAMBER_SIZE: usize = 10_000;
+const CHAMBER_WIDTH: usize = 7;
 
-const ROCK_ORDER: [RockShape; 5] = [
-    RockShape::Minus,
-    RockShape::Plus,
-    RockShape::L,
-    RockShape::Bar,
-    RockShape::Square,
-];
+// columns whose top is further down than this count as equally deep in the cycle state, so a
+// column that never fills doesn't make every state new. rocks come to rest long before falling
+// this far past the top of the tower
+const SURFACE_DEPTH: usize = 64;
 
-#[derive(Debug, Clone, Copy)]
+// the five rocks from the puzzle, in the order they fall
+pub const ROCK_SHAPES: &str = "\
+####
+
+.#.
+###
+.#.
+
+..#
+..#
+###
+
+#
+#
+#
+#
+
+##
+##";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Point {
     x: usize,
     y: usize,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum RockShape {
-    Minus,
-    Plus,
-    L,
-    Bar,
-    Square,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RockShape {
+    // filled cells, relative to the bottom left corner of the shape
+    cells: Vec<Point>,
+    width: usize,
+}
+
+impl RockShape {
+    // '#' is rock and '.' is empty, drawn the right way up
+    pub fn parse(art: &str) -> Self {
+        let rows: Vec<&str> = art
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+
+        let mut cells: Vec<Point> = rows
+            .iter()
+            .rev()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.chars()
+                    .enumerate()
+                    .filter_map(move |(x, chr)| match chr {
+                        '#' => Some(Point { x, y }),
+                        '.' => None,
+                        other => panic!("unrecognized char {other} in rock shape"),
+                    })
+            })
+            .collect();
+
+        assert!(!cells.is_empty(), "rock shape has no rock in it");
+
+        // empty columns on the left and empty rows at the bottom aren't part of the rock
+        let left = cells.iter().map(|cell| cell.x).min().unwrap();
+        let bottom = cells.iter().map(|cell| cell.y).min().unwrap();
+        for cell in &mut cells {
+            cell.x -= left;
+            cell.y -= bottom;
+        }
+
+        let width = cells.iter().map(|cell| cell.x).max().unwrap() + 1;
+
+        Self { cells, width }
+    }
+}
+
+// shapes are separated by blank lines
+pub fn parse_rock_shapes(art: &str) -> Vec<RockShape> {
+    art.trim().split("\n\n").map(RockShape::parse).collect()
 }
 
 #[derive(Debug, Clone, Copy)]
-struct Rock {
+struct Rock<'a> {
     bottom_left: Point,
-    shape: RockShape,
+    shape: &'a RockShape,
 }
 
-impl Rock {
-    fn from(shape: RockShape, chamber: &Chamber) -> Self {
-        let highest_rock = chamber.height();
-        let bottom_left = match shape {
-            RockShape::Bar | RockShape::Minus | RockShape::L | RockShape::Square => Point {
-                x: 2,
-                y: highest_rock + 3,
-            },
-            RockShape::Plus => Point {
-                x: 3,
-                y: highest_rock + 3,
-            },
+impl<'a> Rock<'a> {
+    fn from(shape: &'a RockShape, chamber: &Chamber) -> Self {
+        // two units from the left wall, three units above the highest rock
+        let bottom_left = Point {
+            x: 2,
+            y: chamber.height() + 3,
         };
         Self { bottom_left, shape }
     }
 
+    fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+        self.shape.cells.iter().map(|cell| Point {
+            x: self.bottom_left.x + cell.x,
+            y: self.bottom_left.y + cell.y,
+        })
+    }
+
     fn push(&mut self, chamber: &Chamber, direction: Direction) {
         if !self.touches(chamber, direction) {
             match direction {
@@ -61,59 +126,27 @@ impl Rock {
     }
 
     fn touches(&self, chamber: &Chamber, direction: Direction) -> bool {
-        self.touches_wall(direction) || self.touches_rocks(chamber, direction)
+        self.touches_wall(chamber, direction) || self.touches_rocks(chamber, direction)
     }
 
-    fn touches_wall(&self, side: Direction) -> bool {
-        let (x, _) = (self.bottom_left.x, self.bottom_left.y);
+    fn touches_wall(&self, chamber: &Chamber, side: Direction) -> bool {
         match side {
-            Direction::Left => match self.shape {
-                RockShape::Plus => x == 1,
-                RockShape::Minus | RockShape::L | RockShape::Square | RockShape::Bar => x == 0,
-            },
-            Direction::Right => match self.shape {
-                RockShape::Bar => x == 6,
-                RockShape::Plus | RockShape::Square => x == 5,
-                RockShape::L => x == 4,
-                RockShape::Minus => x == 3,
-            },
+            Direction::Left => self.bottom_left.x == 0,
+            Direction::Right => self.bottom_left.x + self.shape.width == chamber.width,
         }
     }
 
     fn touches_rocks(&self, chamber: &Chamber, direction: Direction) -> bool {
-        let (x, y) = (self.bottom_left.x, self.bottom_left.y);
-        match direction {
-            Direction::Left => {
-                let left_edges = match self.shape {
-                    RockShape::Bar => {
-                        vec![(x - 1, y), (x - 1, y + 1), (x - 1, y + 2), (x - 1, y + 3)]
-                    }
-                    RockShape::Plus => {
-                        vec![(x - 1, y), (x - 2, y + 1), (x - 1, y + 2)]
-                    }
-                    RockShape::Minus => vec![(x - 1, y)],
-                    RockShape::L => vec![(x - 1, y), (x + 1, y + 1), (x + 1, y + 2)],
-                    RockShape::Square => vec![(x - 1, y), (x - 1, y + 1)],
-                };
-
-                chamber.any(left_edges)
-            }
-            Direction::Right => {
-                let right_edges = match self.shape {
-                    RockShape::Bar => {
-                        vec![(x + 1, y), (x + 1, y + 1), (x + 1, y + 2), (x + 1, y + 3)]
-                    }
-                    RockShape::Plus => {
-                        vec![(x + 1, y), (x + 2, y + 1), (x + 1, y + 2)]
-                    }
-                    RockShape::Minus => vec![(x + 4, y)],
-                    RockShape::L => vec![(x + 3, y), (x + 3, y + 1), (x + 3, y + 2)],
-                    RockShape::Square => vec![(x + 2, y), (x + 2, y + 1)],
-                };
-
-                chamber.any(right_edges)
-            }
-        }
+        chamber.any(self.cells().map(|cell| match direction {
+            Direction::Left => Point {
+                x: cell.x - 1,
+                y: cell.y,
+            },
+            Direction::Right => Point {
+                x: cell.x + 1,
+                y: cell.y,
+            },
+        }))
     }
 
     fn touches_bottom(&self, chamber: &Chamber) -> bool {
@@ -121,109 +154,46 @@ impl Rock {
             return true;
         }
 
-        let (x, y) = (self.bottom_left.x, self.bottom_left.y);
-        let bottom_edges = match self.shape {
-            RockShape::Bar => vec![(x, y - 1)],
-            RockShape::Plus => vec![(x, y - 1), (x - 1, y), (x + 1, y)],
-            RockShape::Minus => vec![(x, y - 1), (x + 1, y - 1), (x + 2, y - 1), (x + 3, y - 1)],
-            RockShape::L => vec![(x, y - 1), (x + 1, y - 1), (x + 2, y - 1)],
-            RockShape::Square => vec![(x, y - 1), (x + 1, y - 1)],
-        };
-
-        let bottom_edges = bottom_edges.into_iter().collect();
-
-        chamber.any(bottom_edges)
+        chamber.any(self.cells().map(|cell| Point {
+            x: cell.x,
+            y: cell.y - 1,
+        }))
     }
 }
 
-#[derive(Default)]
 struct Chamber {
-    columns: Vec<[bool; 7]>,
+    width: usize,
+    columns: Vec<Vec<bool>>,
 }
 
 impl Chamber {
-    fn add_empty_rows(&mut self) {
-        // 3 rows above highest rock
-        // +3 for plus and L
-        // +1 for minus
-        // +4 for bar
-        // +2 for square
-        // just add 7 to be safe
-        let n_rows = 7;
-
-        let mut rows_to_add = (0..n_rows).map(|_| [false; 7]).collect();
-
-        self.columns.append(&mut rows_to_add);
-    }
-
-    fn update(&mut self, rock: Rock) {
-        let (x, y) = (rock.bottom_left.x, rock.bottom_left.y);
-        // fill in array based on rock location
-        let positions = match rock.shape {
-            RockShape::Plus => vec![
-                (x, y),
-                (x, y + 1),
-                (x, y + 2),
-                (x - 1, y + 1),
-                (x + 1, y + 1),
-            ],
-            RockShape::Minus => vec![(x, y), (x + 1, y), (x + 2, y), (x + 3, y)],
-            RockShape::L => vec![
-                (x, y),
-                (x + 1, y),
-                (x + 2, y),
-                (x + 2, y + 1),
-                (x + 2, y + 2),
-            ],
-            RockShape::Bar => vec![(x, y), (x, y + 1), (x, y + 2), (x, y + 3)],
-            RockShape::Square => vec![(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)],
-        };
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            columns: Vec::new(),
+        }
+    }
 
-        for (x, y) in positions {
-            assert!(x < 7, "can't insert rock outside of chamber {x},{y}");
+    fn update(&mut self, rock: &Rock) {
+        for Point { x, y } in rock.cells() {
+            assert!(
+                x < self.width,
+                "can't insert rock outside of chamber {x},{y}"
+            );
+            if y >= self.height() {
+                self.columns.resize(y + 1, vec![false; self.width]);
+            }
             assert!(
                 !self.columns[y][x],
                 "can't insert rock into occupied position {x},{y}"
             );
             self.columns[y][x] = true;
         }
-
-        // remove empty rows
-        let last_rock_row = self
-            .columns
-            .iter()
-            .rposition(|row| row.iter().any(|cell| *cell))
-            .unwrap();
-
-        self.columns.drain(last_rock_row + 1..);
     }
 
-    fn any(&self, positions: Vec<(usize, usize)>) -> bool {
-        positions
-            .into_iter()
-            .map(|(x, y)| self.columns[y][x])
-            .any(|b| b)
-    }
-
-    fn insert_rock(
-        &mut self,
-        jet_flows: &mut Cycle<Iter<Direction>>,
-        rocks: &mut Cycle<Iter<RockShape>>,
-    ) {
-        let rock = rocks.next().unwrap();
-        let mut rock = Rock::from(*rock, self);
-        self.add_empty_rows();
-        loop {
-            let jet_flow = jet_flows.next().unwrap();
-            rock.push(self, *jet_flow);
-
-            if rock.touches_bottom(self) {
-                self.update(rock);
-                break;
-            }
-
-            rock.fall();
-        }
+    // positions above the highest rock are always empty
+    fn any(&self, mut positions: impl Iterator<Item = Point>) -> bool {
+        positions.any(|Point { x, y }| self.columns.get(y).is_some_and(|row| row[x]))
     }
 
     fn height(&self) -> usize {
@@ -233,12 +203,12 @@ impl Chamber {
 
 impl fmt::Display for Chamber {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut to_print = vec![String::from("+-------+")];
+        let mut to_print = vec![format!("+{}+", "-".repeat(self.width))];
         for row in &self.columns {
             let row = row
                 .iter()
                 .map(|cell| match cell {
-                    true => "@",
+                    true => "#",
                     false => ".",
                 })
                 .collect::<Vec<&str>>()
@@ -254,7 +224,7 @@ impl fmt::Display for Chamber {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     Left,
     Right,
@@ -270,88 +240,473 @@ impl Direction {
     }
 }
 
-fn fill_chamber(jet_flows: &[Direction], n_rocks: usize) -> Chamber {
-    let mut chamber = Chamber::default();
-    let mut jet_flows = jet_flows.iter().cycle();
-    let mut rocks = ROCK_ORDER.iter().cycle();
+pub struct TowerSimulator {
+    chamber: Chamber,
+    shapes: Vec<RockShape>,
+    jet_flows: Vec<Direction>,
+    next_shape: usize,
+    next_jet_flow: usize,
+    // tower height after each rock, starting with no rocks
+    heights: Vec<usize>,
+}
 
-    for _ in 0..n_rocks {
-        chamber.insert_rock(&mut jet_flows, &mut rocks);
-    }
+// once the simulation reaches the same state again, it repeats the same rocks forever
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    // rocks dropped before the first repeat starts
+    pub start: usize,
+    // rocks dropped in each repeat
+    pub length: usize,
+    pub height_gain: usize,
+}
 
-    chamber
+// which rock and jet come next, and how far down the top of each column is, up to SURFACE_DEPTH
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SimulationState {
+    next_shape: usize,
+    next_jet_flow: usize,
+    surface: Vec<usize>,
 }
 
-fn rocks_until_height(jet_flows: &[Direction], height: usize) -> usize {
-    let mut chamber = Chamber::default();
-    let mut jet_flows = jet_flows.iter().cycle();
-    let mut rocks = ROCK_ORDER.iter().cycle();
+impl TowerSimulator {
+    pub fn new(jet_flows: &str, shapes: Vec<RockShape>, width: usize) -> Self {
+        assert!(!shapes.is_empty(), "need at least one rock shape");
+        for shape in &shapes {
+            assert!(
+                shape.width + 2 <= width,
+                "rock shape is too wide for a chamber {width} wide"
+            );
+        }
 
-    let mut n_rocks = 1;
-    while chamber.height() < height {
-        chamber.insert_rock(&mut jet_flows, &mut rocks);
-        n_rocks += 1;
+        Self {
+            chamber: Chamber::new(width),
+            shapes,
+            jet_flows: jet_flows.trim().chars().map(Direction::from).collect(),
+            next_shape: 0,
+            next_jet_flow: 0,
+            heights: vec![0],
+        }
     }
 
-    n_rocks
+    // the puzzle's rocks and chamber
+    pub fn from_file(file: &str) -> Self {
+        let jet_flows = fs::read_to_string(file).unwrap();
+        Self::new(&jet_flows, parse_rock_shapes(ROCK_SHAPES), CHAMBER_WIDTH)
+    }
+
+    pub fn height(&self) -> usize {
+        self.chamber.height()
+    }
+
+    pub fn drop_rock(&mut self) {
+        self.drop_rock_observed(|_, _, _| {});
+    }
+
+    // calls `observe` when the rock appears, after every jet push and fall, and once it settles
+    fn drop_rock_observed(
+        &mut self,
+        mut observe: impl FnMut(&Chamber, Option<&Rock>, Option<Direction>),
+    ) {
+        let shape = &self.shapes[self.next_shape];
+        self.next_shape = (self.next_shape + 1) % self.shapes.len();
+
+        let mut rock = Rock::from(shape, &self.chamber);
+        observe(&self.chamber, Some(&rock), None);
+        loop {
+            let jet_flow = self.jet_flows[self.next_jet_flow];
+            self.next_jet_flow = (self.next_jet_flow + 1) % self.jet_flows.len();
+            rock.push(&self.chamber, jet_flow);
+            observe(&self.chamber, Some(&rock), Some(jet_flow));
+
+            if rock.touches_bottom(&self.chamber) {
+                self.chamber.update(&rock);
+                observe(&self.chamber, None, None);
+                break;
+            }
+
+            rock.fall();
+            observe(&self.chamber, Some(&rock), None);
+        }
+
+        self.heights.push(self.chamber.height());
+    }
+
+    // every step of the next rocks, each showing only the top `window` rows. with no rows to show
+    // there are no frames, though the rocks still drop
+    pub fn animate(&mut self, n_rocks: usize, window: usize) -> Vec<Frame> {
+        let mut frames = Vec::new();
+        if window == 0 {
+            self.drop_rocks(n_rocks);
+            return frames;
+        }
+
+        for _ in 0..n_rocks {
+            self.drop_rock_observed(|chamber, rock, jet_flow| {
+                frames.push(Frame::new(chamber, rock, jet_flow, window));
+            });
+        }
+        frames
+    }
+
+    pub fn drop_rocks(&mut self, n_rocks: usize) {
+        for _ in 0..n_rocks {
+            self.drop_rock();
+        }
+    }
 }
 
-fn detect_cycle_period(chamber: &Chamber) -> Option<usize> {
-    for cycle_period in 4..=chamber.height() {
-        let pairs: Vec<&[[bool; 7]]> = chamber.columns.chunks_exact(cycle_period).collect();
-        for (i, pair) in pairs.windows(2).enumerate() {
-            let (first, second) = (pair[0], pair[1]);
-            if first == second {
-                println!("CYCLE!: window size {cycle_period} at window {i} starting from index 0");
-                return Some(cycle_period);
+impl TowerSimulator {
+    fn state(&self) -> SimulationState {
+        let height = self.chamber.height();
+        let surface = (0..self.chamber.width)
+            .map(|x| {
+                let top = self.chamber.columns.iter().rposition(|row| row[x]);
+                top.map_or(height, |top| height - 1 - top)
+                    .min(SURFACE_DEPTH)
+            })
+            .collect();
+
+        SimulationState {
+            next_shape: self.next_shape,
+            next_jet_flow: self.next_jet_flow,
+            surface,
+        }
+    }
+
+    // keeps dropping rocks until the state repeats
+    pub fn find_cycle(&mut self) -> Cycle {
+        let mut seen: HashMap<SimulationState, usize> = HashMap::new();
+        let mut rocks = self.heights.len() - 1;
+
+        loop {
+            if let Some(start) = seen.insert(self.state(), rocks) {
+                return Cycle {
+                    start,
+                    length: rocks - start,
+                    height_gain: self.heights[rocks] - self.heights[start],
+                };
             }
+            self.drop_rock();
+            rocks += 1;
         }
     }
-    None
+
+    // height of the tower after that many rocks, skipping over repeats for big numbers
+    pub fn height_after(&mut self, n_rocks: usize) -> usize {
+        if let Some(height) = self.heights.get(n_rocks) {
+            return *height;
+        }
+
+        let cycle = self.find_cycle();
+        if let Some(height) = self.heights.get(n_rocks) {
+            return *height;
+        }
+
+        let cycles = (n_rocks - cycle.start) / cycle.length;
+        let rocks_after_cycles = (n_rocks - cycle.start) % cycle.length;
+
+        self.heights[cycle.start + rocks_after_cycles] + cycles * cycle.height_gain
+    }
+}
+
+impl fmt::Display for TowerSimulator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.chamber)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Air,
+    Rock,
+    FallingRock,
+}
+
+// a snapshot of the top of the chamber while a rock falls
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    // the jet that has just pushed the rock, if any
+    jet_flow: Option<Direction>,
+    // top row first
+    rows: Vec<Vec<Cell>>,
+    // whether the bottom row of the window sits on the floor
+    at_floor: bool,
+}
+
+impl Frame {
+    fn new(
+        chamber: &Chamber,
+        rock: Option<&Rock>,
+        jet_flow: Option<Direction>,
+        window: usize,
+    ) -> Self {
+        let rock_cells: Vec<Point> = rock.map(|rock| rock.cells().collect()).unwrap_or_default();
+
+        let top = rock_cells
+            .iter()
+            .map(|cell| cell.y + 1)
+            .max()
+            .unwrap_or(0)
+            .max(chamber.height());
+        let bottom = top.saturating_sub(window);
+
+        let rows = (bottom..bottom + window)
+            .rev()
+            .map(|y| {
+                (0..chamber.width)
+                    .map(|x| {
+                        if rock_cells.contains(&Point { x, y }) {
+                            Cell::FallingRock
+                        } else if chamber.any([Point { x, y }].into_iter()) {
+                            Cell::Rock
+                        } else {
+                            Cell::Air
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            jet_flow,
+            rows,
+            at_floor: bottom == 0,
+        }
+    }
+
+    // greyscale, with a row for the jet above the chamber and a row for the floor below it
+    fn pixels(&self) -> Vec<Vec<u8>> {
+        let width = self.rows[0].len();
+        let mut pixels = Vec::new();
+
+        let mut jet_row = vec![0; width + 2];
+        match self.jet_flow {
+            Some(Direction::Left) => jet_row[1] = 200,
+            Some(Direction::Right) => jet_row[width] = 200,
+            None => {}
+        }
+        pixels.push(jet_row);
+
+        for row in &self.rows {
+            let mut pixel_row = vec![64];
+            pixel_row.extend(row.iter().map(|cell| match cell {
+                Cell::Air => 0,
+                Cell::Rock => 128,
+                Cell::FallingRock => 255,
+            }));
+            pixel_row.push(64);
+            pixels.push(pixel_row);
+        }
+
+        pixels.push(vec![if self.at_floor { 64 } else { 32 }; width + 2]);
+        pixels
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.rows[0].len();
+
+        let jet = match self.jet_flow {
+            Some(Direction::Left) => "<",
+            Some(Direction::Right) => ">",
+            None => " ",
+        };
+        writeln!(f, " {} ", jet.repeat(width))?;
+
+        for row in &self.rows {
+            let row: String = row
+                .iter()
+                .map(|cell| match cell {
+                    Cell::Air => '.',
+                    Cell::Rock => '#',
+                    Cell::FallingRock => '@',
+                })
+                .collect();
+            writeln!(f, "|{row}|")?;
+        }
+
+        if self.at_floor {
+            writeln!(f, "+{}+", "-".repeat(width))
+        } else {
+            writeln!(f, "|{}|", "~".repeat(width))
+        }
+    }
+}
+
+/// Writes the frames one after the other, separated by blank lines.
+///
+/// # Errors
+///
+/// Returns any error from `writer`.
+pub fn write_text_animation(frames: &[Frame], writer: &mut impl Write) -> io::Result<()> {
+    for frame in frames {
+        writeln!(writer, "{frame}")?;
+    }
+    Ok(())
+}
+
+/// Writes an animated PNG, with every cell drawn `scale` pixels wide. The image data is stored
+/// without compression, which keeps the encoder small at the cost of bigger files.
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error when there are no frames or `scale` is 0, and otherwise
+/// any error from `writer`.
+pub fn write_png_animation(
+    frames: &[Frame],
+    scale: usize,
+    frame_millis: u16,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    if frames.is_empty() || scale == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "an animation needs at least one frame and a scale of at least 1",
+        ));
+    }
+
+    let images: Vec<Vec<Vec<u8>>> = frames
+        .iter()
+        .map(|frame| {
+            frame
+                .pixels()
+                .into_iter()
+                .flat_map(|row| {
+                    let row: Vec<u8> = row
+                        .into_iter()
+                        .flat_map(|pixel| std::iter::repeat_n(pixel, scale))
+                        .collect();
+                    std::iter::repeat_n(row, scale)
+                })
+                .collect()
+        })
+        .collect();
+
+    let height = u32::try_from(images[0].len()).unwrap();
+    let width = u32::try_from(images[0][0].len()).unwrap();
+    let frame_count = u32::try_from(images.len()).unwrap();
+
+    writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::new();
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bit greyscale, no interlacing
+    header.extend([8, 0, 0, 0, 0]);
+    write_png_chunk(writer, *b"IHDR", &header)?;
+
+    // loop forever
+    let mut animation_control = frame_count.to_be_bytes().to_vec();
+    animation_control.extend(0_u32.to_be_bytes());
+    write_png_chunk(writer, *b"acTL", &animation_control)?;
+
+    let mut sequence_number: u32 = 0;
+    for (index, image) in images.iter().enumerate() {
+        let mut frame_control = sequence_number.to_be_bytes().to_vec();
+        frame_control.extend(width.to_be_bytes());
+        frame_control.extend(height.to_be_bytes());
+        frame_control.extend(0_u32.to_be_bytes());
+        frame_control.extend(0_u32.to_be_bytes());
+        frame_control.extend(frame_millis.to_be_bytes());
+        frame_control.extend(1000_u16.to_be_bytes());
+        frame_control.extend([0, 0]);
+        write_png_chunk(writer, *b"fcTL", &frame_control)?;
+        sequence_number += 1;
+
+        // every row starts with filter type 0, meaning no filter
+        let raw: Vec<u8> = image
+            .iter()
+            .flat_map(|row| std::iter::once(0).chain(row.iter().copied()))
+            .collect();
+        let data = zlib_stored(&raw);
+
+        if index == 0 {
+            write_png_chunk(writer, *b"IDAT", &data)?;
+        } else {
+            let mut frame_data = sequence_number.to_be_bytes().to_vec();
+            frame_data.extend(data);
+            write_png_chunk(writer, *b"fdAT", &frame_data)?;
+            sequence_number += 1;
+        }
+    }
+
+    write_png_chunk(writer, *b"IEND", &[])
+}
+
+fn write_png_chunk(writer: &mut impl Write, kind: [u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&u32::try_from(data.len()).unwrap().to_be_bytes())?;
+    writer.write_all(&kind)?;
+    writer.write_all(data)?;
+
+    let mut crc = !0_u32;
+    for byte in kind.iter().chain(data) {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    writer.write_all(&(!crc).to_be_bytes())
+}
+
+// a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+
+    let blocks: Vec<&[u8]> = data.chunks(usize::from(u16::MAX)).collect();
+    for (index, block) in blocks.iter().enumerate() {
+        let is_last = index + 1 == blocks.len();
+        let length = u16::try_from(block.len()).unwrap();
+        stream.push(u8::from(is_last));
+        stream.extend(length.to_le_bytes());
+        stream.extend((!length).to_le_bytes());
+        stream.extend(*block);
+    }
+    if blocks.is_empty() {
+        stream.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for byte in data {
+        a = (a + u32::from(*byte)) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    stream.extend(((b << 16) | a).to_be_bytes());
+
+    stream
 }
 
 pub fn count_tower_height(file: &str, n_rocks: usize) -> usize {
+    let mut simulator = TowerSimulator::from_file(file);
+    simulator.drop_rocks(n_rocks);
+    simulator.height()
+}
+
+pub fn count_tower_height_with(
+    file: &str,
+    n_rocks: usize,
+    shapes: &str,
+    chamber_width: usize,
+) -> usize {
     let jet_flows = fs::read_to_string(file).unwrap();
-    let jet_flows: Vec<Direction> = jet_flows.trim().chars().map(Direction::from).collect();
-    let chamber = fill_chamber(&jet_flows, n_rocks);
-    chamber.height()
+    let mut simulator = TowerSimulator::new(&jet_flows, parse_rock_shapes(shapes), chamber_width);
+    simulator.drop_rocks(n_rocks);
+    simulator.height()
 }
 
 pub fn count_very_tall_tower_height(file: &str, n_rocks: usize) -> usize {
-    let jet_flows = fs::read_to_string(file).unwrap();
-    let jet_flows: Vec<Direction> = jet_flows.trim().chars().map(Direction::from).collect();
-
-    let chamber = fill_chamber(&jet_flows, TEST_CHAMBER_SIZE);
-    let cycle_period = detect_cycle_period(&chamber).unwrap();
-
-    // for some weird reason, the repeats only occur after one cycle. So
-    // cycle 0 != cycle 1, but cycle 1 == cycle 2, cycle 2 == cycle 3, etc...
-    // So first we calculate the rocks in the first cycle
-    let rocks_before_cycles = rocks_until_height(&jet_flows, cycle_period);
-
-    // And the rocks in the next cycle (which will repeat indefinitely)
-    let rocks_after_one_cycle = rocks_until_height(&jet_flows, cycle_period * 2);
-    let rocks_in_one_cycle = rocks_after_one_cycle - rocks_before_cycles;
-
-    // See how many full cycles will occur
-    let rocks_left = n_rocks - rocks_before_cycles;
-    let cycles = rocks_left / rocks_in_one_cycle;
-    let rocks_in_cycles = rocks_in_one_cycle * cycles;
-    let rocks_after_cycles = n_rocks - rocks_in_cycles - rocks_before_cycles;
-
-    // get height of last unfinished cycle
-    // a little hacky but it's fine for now
-    let chamber = fill_chamber(
-        &jet_flows,
-        rocks_before_cycles + rocks_in_one_cycle + rocks_after_cycles,
-    );
-    let unfinished_cycle_height = chamber.height() - cycle_period * 2;
-
-    cycle_period + cycle_period * cycles + unfinished_cycle_height
+    TowerSimulator::from_file(file).height_after(n_rocks)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io;
+
+    use crate::day17::{RockShape, TowerSimulator};
     use crate::{day17, fetch_input};
 
     #[test]
@@ -365,7 +720,7 @@ mod tests {
         for test in tests {
             let (file, want) = test;
             let got = day17::count_tower_height(file, n_rocks);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
 
@@ -376,14 +731,143 @@ mod tests {
         let n_rocks = 1_000_000_000_000;
 
         let tests = vec![
-            ("example/day17.txt", 1514285714288),
-            ("input/day17.txt", 1577077363915),
+            ("example/day17.txt", 1_514_285_714_288),
+            ("input/day17.txt", 1_577_077_363_915),
         ];
 
         for test in tests {
             let (file, want) = test;
             let got = day17::count_very_tall_tower_height(file, n_rocks);
-            assert_eq!(got, want, "got {got}, wanted {want}")
+            assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
+
+    #[test]
+    fn find_cycle() {
+        fetch_input(17);
+
+        let mut simulator = TowerSimulator::from_file("example/day17.txt");
+        let cycle = simulator.find_cycle();
+        assert_eq!((cycle.length, cycle.height_gain), (35, 53), "got {cycle:?}");
+
+        // skipping ahead through the cycles matches dropping every rock
+        for file in ["example/day17.txt", "input/day17.txt"] {
+            let mut skipping = TowerSimulator::from_file(file);
+            skipping.find_cycle();
+            let got = skipping.height_after(5000);
+            let want = day17::count_tower_height(file, 5000);
+            assert_eq!(got, want, "got {got}, wanted {want}, for {file}");
+        }
+
+        // every jet pushes right, so the leftmost columns never get a rock
+        let shapes = || day17::parse_rock_shapes(day17::ROCK_SHAPES);
+        let mut skipping = TowerSimulator::new(">", shapes(), 7);
+        skipping.find_cycle();
+        let got = skipping.height_after(5000);
+        let mut dropping = TowerSimulator::new(">", shapes(), 7);
+        dropping.drop_rocks(5000);
+        let want = dropping.height();
+        assert_eq!(got, want, "got {got}, wanted {want}");
+    }
+
+    #[test]
+    fn animate() {
+        let mut simulator = TowerSimulator::from_file("example/day17.txt");
+        simulator.drop_rocks(1);
+        let frames = simulator.animate(1, 5);
+
+        // appears, 4 pushes and 3 falls, then settles
+        assert_eq!(frames.len(), 9);
+
+        let tests = vec![
+            (
+                1,
+                vec![
+                    " <<<<<<< ",
+                    "|..@....|",
+                    "|.@@@...|",
+                    "|..@....|",
+                    "|.......|",
+                    "|.......|",
+                    "|~~~~~~~|",
+                ],
+            ),
+            (
+                8,
+                vec![
+                    "         ",
+                    "|.......|",
+                    "|...#...|",
+                    "|..###..|",
+                    "|...#...|",
+                    "|..####.|",
+                    "+-------+",
+                ],
+            ),
+        ];
+
+        for test in tests {
+            let (index, want) = test;
+            let got = frames[index].to_string();
+            let got: Vec<&str> = got.lines().collect();
+
+            assert_eq!(
+                got, want,
+                "got {got:#?}, wanted {want:#?}, for frame {index}"
+            );
+        }
+
+        let mut text = Vec::new();
+        day17::write_text_animation(&frames, &mut text).unwrap();
+        assert_eq!(String::from_utf8(text).unwrap().matches("|~").count(), 4);
+
+        let mut image = Vec::new();
+        day17::write_png_animation(&frames, 2, 100, &mut image).unwrap();
+        assert!(image.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        assert!(image.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+
+        let error = day17::write_png_animation(&[], 2, 100, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        // a window without rows has nothing to show
+        assert!(simulator.animate(1, 0).is_empty());
+    }
+
+    #[test]
+    fn count_tower_height_with() {
+        let tests = vec![
+            (day17::ROCK_SHAPES, 7, 2022, 3068),
+            // bars this wide always overlap the one below, so they stack straight up
+            ("#####", 7, 100, 100),
+            ("#######", 9, 10, 10),
+        ];
+
+        for test in tests {
+            let (shapes, width, n_rocks, want) = test;
+            let got = day17::count_tower_height_with("example/day17.txt", n_rocks, shapes, width);
+            assert_eq!(got, want, "got {got}, wanted {want}, for {shapes}");
+        }
+
+        // custom tetrominoes in a wider chamber, never taller than stacking them all up
+        let tetrominoes = "####\n\n##\n##\n\n#.\n#.\n##\n\n.##\n##.\n\n###\n.#.";
+        let got = day17::count_tower_height_with("example/day17.txt", 50, tetrominoes, 9);
+        assert!((10..=100).contains(&got), "got {got}");
+    }
+
+    #[test]
+    fn parse_rock_shapes() {
+        let shapes = day17::parse_rock_shapes(day17::ROCK_SHAPES);
+        assert_eq!(shapes.len(), 5);
+
+        // the L is stored the right way up, from its bottom left corner
+        let l = RockShape::parse("..#\n..#\n###");
+        let want = RockShape::parse("  ..#\n  ..#\n  ###  ");
+        assert_eq!(l, want);
+        assert_eq!(l, shapes[2]);
+
+        // empty space around the rock is trimmed, so it still reaches the wall and the floor
+        assert_eq!(RockShape::parse(".#\n.#"), RockShape::parse("#\n#"));
+        assert_eq!(RockShape::parse("#\n."), RockShape::parse("#"));
+    }
 }
@@ -1,30 +1,20 @@
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::VecDeque,
     fs,
+    io::{self, Write},
 };
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone)]
-struct Cube {
-    x: isize,
-    y: isize,
-    z: isize,
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct Cube {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
 }
 
 impl Cube {
-    fn new(x: isize, y: isize, z: isize) -> Self {
+    pub fn new(x: isize, y: isize, z: isize) -> Self {
         Cube { x, y, z }
     }
-
-    fn neighbours(&self) -> Vec<Self> {
-        vec![
-            Cube::new(self.x + 1, self.y, self.z),
-            Cube::new(self.x - 1, self.y, self.z),
-            Cube::new(self.x, self.y + 1, self.z),
-            Cube::new(self.x, self.y - 1, self.z),
-            Cube::new(self.x, self.y, self.z + 1),
-            Cube::new(self.x, self.y, self.z - 1),
-        ]
-    }
 }
 
 impl From<&str> for Cube {
@@ -38,136 +28,412 @@ impl From<&str> for Cube {
     }
 }
 
-struct Cubes(HashSet<Cube>);
-
-impl Cubes {
-    fn from_file(file: &str) -> Self {
-        Self(
-            fs::read_to_string(file)
-                .unwrap()
-                .lines()
-                .map(Cube::from)
-                .collect(),
-        )
-    }
+// inclusive on both ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: Cube,
+    pub max: Cube,
+}
 
-    fn count_sides_touching(&self, cube_set: &Cubes) -> usize {
-        self.0
-            .iter()
-            .map(|cube| {
-                cube.neighbours()
-                    .iter()
-                    .filter(|neighbour| cube_set.0.contains(neighbour))
-                    .count()
-            })
-            .sum()
+impl BoundingBox {
+    fn around(cube: Cube) -> Self {
+        BoundingBox {
+            min: cube,
+            max: cube,
+        }
     }
 
-    fn surface_area(&self) -> usize {
-        let total_sides = self.0.len() * 6;
-        let sides_touching = self.count_sides_touching(self);
-        total_sides - sides_touching
+    fn extend(&mut self, cube: Cube) {
+        self.min = Cube::new(
+            self.min.x.min(cube.x),
+            self.min.y.min(cube.y),
+            self.min.z.min(cube.z),
+        );
+        self.max = Cube::new(
+            self.max.x.max(cube.x),
+            self.max.y.max(cube.y),
+            self.max.z.max(cube.z),
+        );
     }
 }
 
-pub fn surface_area(file: &str) -> usize {
-    Cubes::from_file(file).surface_area()
+// a face-connected group of cubes that are either all lava or all air
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub volume: usize,
+    pub surface_area: usize,
+    pub bounding_box: BoundingBox,
 }
 
-pub fn external_surface_area(file: &str) -> usize {
-    let cubes = Cubes::from_file(file);
-
-    // find bounding cube
+// lava stored as one bit per cube of the bounding box, grown by a layer of air on every side so
+// that the air outside the droplet is always connected
+pub struct VoxelModel {
+    origin: Cube,
+    size: [usize; 3],
+    lava: Vec<u64>,
+}
 
-    let mut max_x = isize::MIN;
-    let mut min_x = isize::MAX;
-    let mut max_y = isize::MIN;
-    let mut min_y = isize::MAX;
-    let mut max_z = isize::MIN;
-    let mut min_z = isize::MAX;
+impl VoxelModel {
+    pub fn from_file(file: &str) -> Self {
+        let cubes: Vec<Cube> = fs::read_to_string(file)
+            .unwrap()
+            .lines()
+            .map(Cube::from)
+            .collect();
+        Self::new(&cubes)
+    }
 
-    for cube in &cubes.0 {
-        if cube.x > max_x {
-            max_x = cube.x;
+    pub fn new(cubes: &[Cube]) -> Self {
+        let mut bounds = BoundingBox::around(cubes.first().copied().unwrap_or(Cube::new(0, 0, 0)));
+        for &cube in cubes {
+            bounds.extend(cube);
         }
-        if cube.x < min_x {
-            min_x = cube.x;
-        }
-        if cube.y > max_y {
-            max_y = cube.y;
-        }
-        if cube.y < min_y {
-            min_y = cube.y;
-        }
-        if cube.z > max_z {
-            max_z = cube.z;
+
+        let origin = Cube::new(bounds.min.x - 1, bounds.min.y - 1, bounds.min.z - 1);
+        let size = [
+            (bounds.max.x - bounds.min.x + 3).unsigned_abs(),
+            (bounds.max.y - bounds.min.y + 3).unsigned_abs(),
+            (bounds.max.z - bounds.min.z + 3).unsigned_abs(),
+        ];
+
+        let mut model = VoxelModel {
+            origin,
+            size,
+            lava: vec![0; (size[0] * size[1] * size[2]).div_ceil(64)],
+        };
+        for &cube in cubes {
+            let index = model.index(cube).unwrap();
+            model.lava[index / 64] |= 1 << (index % 64);
         }
-        if cube.z < min_z {
-            min_z = cube.z;
+        model
+    }
+
+    fn index(&self, cube: Cube) -> Option<usize> {
+        let x = usize::try_from(cube.x - self.origin.x).ok()?;
+        let y = usize::try_from(cube.y - self.origin.y).ok()?;
+        let z = usize::try_from(cube.z - self.origin.z).ok()?;
+        if x >= self.size[0] || y >= self.size[1] || z >= self.size[2] {
+            return None;
         }
+        Some((z * self.size[1] + y) * self.size[0] + x)
     }
 
-    // add one layer of space
+    fn cube(&self, index: usize) -> Cube {
+        let x = index % self.size[0];
+        let y = index / self.size[0] % self.size[1];
+        let z = index / self.size[0] / self.size[1];
+        Cube::new(
+            self.origin.x + isize::try_from(x).unwrap(),
+            self.origin.y + isize::try_from(y).unwrap(),
+            self.origin.z + isize::try_from(z).unwrap(),
+        )
+    }
 
-    let max_x = max_x + 1;
-    let min_x = min_x - 1;
-    let max_y = max_y + 1;
-    let min_y = min_y - 1;
-    let max_z = max_z + 1;
-    let min_z = min_z - 1;
+    fn is_lava(&self, index: usize) -> bool {
+        self.lava[index / 64] & (1 << (index % 64)) != 0
+    }
 
-    // floodfill
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let cube = self.cube(index);
+        [
+            Cube::new(cube.x + 1, cube.y, cube.z),
+            Cube::new(cube.x - 1, cube.y, cube.z),
+            Cube::new(cube.x, cube.y + 1, cube.z),
+            Cube::new(cube.x, cube.y - 1, cube.z),
+            Cube::new(cube.x, cube.y, cube.z + 1),
+            Cube::new(cube.x, cube.y, cube.z - 1),
+        ]
+        .into_iter()
+        .filter_map(|neighbour| self.index(neighbour))
+    }
 
-    let start = Cube::new(min_x, min_y, min_z);
+    pub fn contains(&self, cube: Cube) -> bool {
+        self.index(cube).is_some_and(|index| self.is_lava(index))
+    }
 
-    let mut frontier = VecDeque::new();
-    let mut exterior_spaces = HashSet::new();
+    // number of lava cubes
+    pub fn volume(&self) -> usize {
+        self.lava
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
 
-    frontier.push_back(start.clone());
-    exterior_spaces.insert(start);
+    // lava plus the air trapped inside it
+    pub fn total_volume(&self) -> usize {
+        self.volume()
+            + self
+                .pockets()
+                .iter()
+                .map(|pocket| pocket.volume)
+                .sum::<usize>()
+    }
+
+    pub fn surface_area(&self) -> usize {
+        self.lava_components()
+            .iter()
+            .map(|component| component.surface_area)
+            .sum()
+    }
+
+    pub fn external_surface_area(&self) -> usize {
+        self.surface_area()
+            - self
+                .pockets()
+                .iter()
+                .map(|pocket| pocket.surface_area)
+                .sum::<usize>()
+    }
+
+    pub fn lava_components(&self) -> Vec<Region> {
+        self.regions(true)
+    }
+
+    // air that can't reach the outside of the droplet
+    pub fn pockets(&self) -> Vec<Region> {
+        // the corner of the grown bounding box is always outside air, and is the first air found
+        let mut pockets = self.regions(false);
+        pockets.remove(0);
+        pockets
+    }
 
-    while let Some(current) = frontier.pop_front() {
-        for next in current.neighbours() {
-            let out_of_bounds = next.x < min_x
-                || next.x > max_x
-                || next.y < min_y
-                || next.y > max_y
-                || next.z < min_z
-                || next.z > max_z;
+    fn regions(&self, lava: bool) -> Vec<Region> {
+        let n_cells = self.size[0] * self.size[1] * self.size[2];
+        let mut visited = vec![0_u64; self.lava.len()];
+
+        (0..n_cells)
+            .filter(|&index| self.is_lava(index) == lava)
+            .filter_map(|start| {
+                if visited[start / 64] & (1 << (start % 64)) != 0 {
+                    None
+                } else {
+                    Some(self.flood(start, &mut visited))
+                }
+            })
+            .collect()
+    }
 
-            if out_of_bounds || cubes.0.contains(&next) || exterior_spaces.contains(&next) {
-                continue;
+    // the region holding `start`, marking each of its cubes as visited
+    fn flood(&self, start: usize, visited: &mut [u64]) -> Region {
+        let lava = self.is_lava(start);
+        let mut region = Region {
+            volume: 0,
+            surface_area: 0,
+            bounding_box: BoundingBox::around(self.cube(start)),
+        };
+
+        let mut frontier = VecDeque::from([start]);
+        visited[start / 64] |= 1 << (start % 64);
+
+        while let Some(current) = frontier.pop_front() {
+            region.volume += 1;
+            region.bounding_box.extend(self.cube(current));
+
+            for next in self.neighbours(current) {
+                if self.is_lava(next) != lava {
+                    region.surface_area += 1;
+                } else if visited[next / 64] & (1 << (next % 64)) == 0 {
+                    visited[next / 64] |= 1 << (next % 64);
+                    frontier.push_back(next);
+                }
             }
+        }
 
-            frontier.push_back(next.clone());
-            exterior_spaces.insert(next);
+        region
+    }
+
+    // the lava faces that touch outside air, with neighbouring faces in the same plane merged
+    // into rectangles
+    pub fn exterior_mesh(&self) -> Mesh {
+        let mut exterior = vec![0_u64; self.lava.len()];
+        self.flood(0, &mut exterior);
+        let is_exterior = |cube: Cube| {
+            self.index(cube)
+                .is_some_and(|index| exterior[index / 64] & (1 << (index % 64)) != 0)
+        };
+
+        let mut quads = Vec::new();
+        for axis in 0..3 {
+            // the plane of each face is spanned by the two other axes, in cyclic order so that
+            // the corners wind counterclockwise seen from the positive side of `axis`
+            let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+            let (u_size, v_size) = (self.size[u_axis], self.size[v_axis]);
+
+            for direction in [1, -1] {
+                for layer in 0..self.size[axis] {
+                    let cube_at = |u: usize, v: usize| {
+                        let mut position = [0; 3];
+                        position[axis] = layer;
+                        position[u_axis] = u;
+                        position[v_axis] = v;
+                        Cube::new(
+                            self.origin.x + isize::try_from(position[0]).unwrap(),
+                            self.origin.y + isize::try_from(position[1]).unwrap(),
+                            self.origin.z + isize::try_from(position[2]).unwrap(),
+                        )
+                    };
+
+                    let mut faces: Vec<bool> = (0..v_size)
+                        .flat_map(|v| (0..u_size).map(move |u| (u, v)))
+                        .map(|(u, v)| {
+                            let cube = cube_at(u, v);
+                            let mut outside = [cube.x, cube.y, cube.z];
+                            outside[axis] += direction;
+                            self.contains(cube)
+                                && is_exterior(Cube::new(outside[0], outside[1], outside[2]))
+                        })
+                        .collect();
+
+                    for v in 0..v_size {
+                        for u in 0..u_size {
+                            if !faces[v * u_size + u] {
+                                continue;
+                            }
+
+                            let width = (u..u_size)
+                                .take_while(|&end| faces[v * u_size + end])
+                                .count();
+                            let height = (v..v_size)
+                                .take_while(|&end| {
+                                    (u..u + width).all(|column| faces[end * u_size + column])
+                                })
+                                .count();
+
+                            for row in v..v + height {
+                                for column in u..u + width {
+                                    faces[row * u_size + column] = false;
+                                }
+                            }
+
+                            let low = cube_at(u, v);
+                            let high = cube_at(u + width, v + height);
+                            let (low, high) = ([low.x, low.y, low.z], [high.x, high.y, high.z]);
+                            let plane = if direction > 0 {
+                                low[axis] + 1
+                            } else {
+                                low[axis]
+                            };
+
+                            let corner = |u_end: &[isize; 3], v_end: &[isize; 3]| {
+                                let mut corner = [0; 3];
+                                corner[axis] = plane;
+                                corner[u_axis] = u_end[u_axis];
+                                corner[v_axis] = v_end[v_axis];
+                                corner
+                            };
+                            let mut corners = [
+                                corner(&low, &low),
+                                corner(&high, &low),
+                                corner(&high, &high),
+                                corner(&low, &high),
+                            ];
+                            if direction < 0 {
+                                corners.reverse();
+                            }
+
+                            let mut normal = [0; 3];
+                            normal[axis] = direction;
+                            quads.push(Quad { corners, normal });
+                        }
+                    }
+                }
+            }
         }
+
+        Mesh { quads }
     }
+}
 
-    // determine internal spaces
+// a rectangle of cube faces, with corners counterclockwise seen from outside the droplet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quad {
+    pub corners: [[isize; 3]; 4],
+    pub normal: [isize; 3],
+}
 
-    let mut all_cubes = HashSet::new();
-    for x in min_x..=max_x {
-        for y in min_y..=max_y {
-            for z in min_z..=max_z {
-                all_cubes.insert(Cube::new(x, y, z));
+impl Quad {
+    pub fn area(&self) -> usize {
+        let [first, _, third, _] = self.corners;
+        (0..3)
+            .map(|axis| first[axis].abs_diff(third[axis]))
+            .filter(|&length| length != 0)
+            .product()
+    }
+}
+
+pub struct Mesh {
+    quads: Vec<Quad>,
+}
+
+impl Mesh {
+    pub fn quads(&self) -> &[Quad] {
+        &self.quads
+    }
+
+    pub fn area(&self) -> usize {
+        self.quads.iter().map(Quad::area).sum()
+    }
+
+    /// Writes Wavefront OBJ, with each quad as one face over its own four vertices.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `writer`.
+    pub fn write_obj(&self, writer: &mut impl Write) -> io::Result<()> {
+        for quad in &self.quads {
+            for [x, y, z] in quad.corners {
+                writeln!(writer, "v {x} {y} {z}")?;
             }
         }
+        for index in 0..self.quads.len() {
+            let first = index * 4 + 1;
+            writeln!(
+                writer,
+                "f {} {} {} {}",
+                first,
+                first + 1,
+                first + 2,
+                first + 3
+            )?;
+        }
+        Ok(())
     }
 
-    let all_spaces: HashSet<Cube> = all_cubes.difference(&cubes.0).cloned().collect();
-    let interior_spaces: HashSet<Cube> = all_spaces.difference(&exterior_spaces).cloned().collect();
-
-    let interior_spaces = Cubes(interior_spaces);
+    /// Writes ASCII STL, splitting each quad into two triangles.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from `writer`.
+    pub fn write_stl(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "solid droplet")?;
+        for quad in &self.quads {
+            let [a, b, c, d] = quad.corners;
+            let [nx, ny, nz] = quad.normal;
+            for triangle in [[a, b, c], [a, c, d]] {
+                writeln!(writer, "facet normal {nx} {ny} {nz}")?;
+                writeln!(writer, "outer loop")?;
+                for [x, y, z] in triangle {
+                    writeln!(writer, "vertex {x} {y} {z}")?;
+                }
+                writeln!(writer, "endloop")?;
+                writeln!(writer, "endfacet")?;
+            }
+        }
+        writeln!(writer, "endsolid droplet")
+    }
+}
 
-    let surface_area = cubes.surface_area();
-    let interior_surface_area = interior_spaces.count_sides_touching(&cubes);
+pub fn surface_area(file: &str) -> usize {
+    VoxelModel::from_file(file).surface_area()
+}
 
-    surface_area - interior_surface_area
+pub fn external_surface_area(file: &str) -> usize {
+    VoxelModel::from_file(file).external_surface_area()
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::day18::{BoundingBox, Cube, Region, VoxelModel};
     use crate::{day18, fetch_input};
 
     #[test]
@@ -191,4 +457,62 @@ mod tests {
             assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
+
+    #[test]
+    fn exterior_mesh() {
+        fetch_input(18);
+        let tests = vec![("example/day18.txt", 58), ("input/day18.txt", 2018)];
+
+        for (infile, want) in tests {
+            let mesh = VoxelModel::from_file(infile).exterior_mesh();
+            let got = mesh.area();
+            assert_eq!(got, want, "got {got}, wanted {want}");
+        }
+
+        // two cubes side by side make a box, whose long faces are merged
+        let mesh = VoxelModel::new(&[Cube::new(0, 0, 0), Cube::new(1, 0, 0)]).exterior_mesh();
+        assert_eq!(mesh.quads().len(), 6);
+        assert_eq!(mesh.area(), 10);
+
+        let top = mesh
+            .quads()
+            .iter()
+            .find(|quad| quad.normal == [0, 0, 1])
+            .unwrap();
+        assert_eq!(top.corners, [[0, 0, 1], [2, 0, 1], [2, 1, 1], [0, 1, 1]]);
+
+        let mut obj = Vec::new();
+        mesh.write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with("v ")).count(),
+            24
+        );
+        assert!(obj.ends_with("f 21 22 23 24\n"));
+    }
+
+    #[test]
+    fn voxel_model() {
+        let model = VoxelModel::from_file("example/day18.txt");
+
+        let got = model.pockets();
+        let want = vec![Region {
+            volume: 1,
+            surface_area: 6,
+            bounding_box: BoundingBox {
+                min: Cube::new(2, 2, 5),
+                max: Cube::new(2, 2, 5),
+            },
+        }];
+        assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+
+        assert_eq!(model.volume(), 13);
+        assert_eq!(model.total_volume(), 14);
+        assert!(model.contains(Cube::new(2, 2, 6)) && !model.contains(Cube::new(2, 2, 5)));
+
+        let components = model.lava_components();
+        let got: Vec<usize> = components.iter().map(|c| c.volume).collect();
+        // the bottom half is one piece, the five cubes around the pocket touch it only at edges
+        assert_eq!(got, vec![8, 1, 1, 1, 1, 1]);
+    }
 }
@@ -11,194 +11,500 @@
 // 2) the algorithm is not so complicated - you aren't going to find the magic answer in a journal
 // article from 2007 and 3) consider the domain very specifically and don't over generalize the
 // problem.
-use std::fs;
+use std::{
+    error::Error,
+    fmt, fs,
+    num::NonZero,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
-#[derive(Debug)]
-struct Blueprint {
-    id: isize,
-    ore_for_orebot: isize,
-    ore_for_claybot: isize,
-    ore_for_obsidianbot: isize,
-    clay_for_obsidianbot: isize,
-    ore_for_geodebot: isize,
-    obsidian_for_geodebot: isize,
+// a robot that collects one `resource` per minute, built from the listed amounts of others
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipe {
+    pub robot: usize,
+    pub costs: Vec<(usize, isize)>,
+}
+
+// Resources are numbered in the order they first appear. The first robot listed is the one the
+// search starts with, and the last one collects the resource being maximised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blueprint {
+    pub id: isize,
+    pub resources: Vec<String>,
+    pub recipes: Vec<Recipe>,
 }
 
 impl Blueprint {
-    fn new(line: &str) -> Self {
-        let line = line
-            .replace("Blueprint", "")
-            .replace(':', "")
-            .replace("Each", "")
-            .replace("ore", "")
-            .replace("robot", "")
-            .replace("costs", "")
-            .replace('.', "")
-            .replace("clay", "")
-            .replace("obsidian", "")
-            .replace("geode", "")
-            .replace("and", "");
-        let mut numbers = line.split_whitespace();
-        Self {
-            id: numbers.next().unwrap().parse().unwrap(),
-            ore_for_orebot: numbers.next().unwrap().parse().unwrap(),
-            ore_for_claybot: numbers.next().unwrap().parse().unwrap(),
-            ore_for_obsidianbot: numbers.next().unwrap().parse().unwrap(),
-            clay_for_obsidianbot: numbers.next().unwrap().parse().unwrap(),
-            ore_for_geodebot: numbers.next().unwrap().parse().unwrap(),
-            obsidian_for_geodebot: numbers.next().unwrap().parse().unwrap(),
+    fn resource_index(resources: &mut Vec<String>, name: &str) -> usize {
+        resources
+            .iter()
+            .position(|resource| resource == name)
+            .unwrap_or_else(|| {
+                resources.push(name.to_string());
+                resources.len() - 1
+            })
+    }
+
+    fn starting_robot(&self) -> usize {
+        self.recipes[0].robot
+    }
+
+    fn goal(&self) -> usize {
+        self.recipes[self.recipes.len() - 1].robot
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBlueprintError {
+    pub line_number: usize,
+    pub line: String,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseBlueprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} in {:?}",
+            self.line_number, self.reason, self.line
+        )
+    }
+}
+
+impl Error for ParseBlueprintError {}
+
+fn parse_blueprints(filename: &str) -> Vec<Blueprint> {
+    let input = fs::read_to_string(filename).unwrap();
+    parse_blueprint_list(&input).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Reads one blueprint per line, as `Blueprint <id>:` followed by sentences of the form
+/// `Each <resource> robot costs <amount> <resource> and <amount> <resource>.` with any number of
+/// costs, separated by `and` or commas. Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns the first line that can't be read as a blueprint.
+pub fn parse_blueprint_list(input: &str) -> Result<Vec<Blueprint>, ParseBlueprintError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            parse_blueprint(line).map_err(|reason| ParseBlueprintError {
+                line_number: index + 1,
+                line: line.to_string(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+fn parse_blueprint(line: &str) -> Result<Blueprint, String> {
+    let (id, sentences) = line.split_once(':').ok_or("missing ':' after the id")?;
+    let id = id
+        .trim()
+        .strip_prefix("Blueprint")
+        .ok_or("missing 'Blueprint'")?
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid id {id:?}"))?;
+
+    let mut resources = Vec::new();
+    let mut recipes = Vec::new();
+
+    for sentence in sentences.split('.').map(str::trim) {
+        if sentence.is_empty() {
+            continue;
         }
+
+        let (robot, costs) = sentence
+            .strip_prefix("Each ")
+            .and_then(|sentence| sentence.split_once(" robot costs "))
+            .ok_or_else(|| format!("expected 'Each <resource> robot costs' in {sentence:?}"))?;
+        let robot = Blueprint::resource_index(&mut resources, robot.trim());
+
+        let costs = costs
+            .split(" and ")
+            .flat_map(|costs| costs.split(','))
+            .map(|cost| {
+                let (amount, resource) = cost
+                    .trim()
+                    .split_once(' ')
+                    .ok_or_else(|| format!("expected '<amount> <resource>' in {cost:?}"))?;
+                let amount = amount
+                    .parse()
+                    .map_err(|_| format!("invalid amount {amount:?}"))?;
+                Ok((
+                    Blueprint::resource_index(&mut resources, resource.trim()),
+                    amount,
+                ))
+            })
+            .collect::<Result<_, String>>()?;
+
+        recipes.push(Recipe { robot, costs });
     }
+
+    if recipes.is_empty() {
+        return Err("no robots".to_string());
+    }
+
+    Ok(Blueprint {
+        id,
+        resources,
+        recipes,
+    })
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct State {
-    elapsed_minutes: isize,
-    ores: isize,
-    clays: isize,
-    obsidians: isize,
-    geodes: isize,
-    orebots: isize,
-    claybots: isize,
-    obsidianbots: isize,
-    geodebots: isize,
+// resources collected and robots owned at the end of a minute, indexed like the blueprint's
+// resources
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct State {
+    pub elapsed_minutes: isize,
+    pub resources: Vec<isize>,
+    pub robots: Vec<isize>,
 }
 
 impl State {
-    fn new() -> Self {
+    fn new(blueprint: &Blueprint) -> Self {
+        let mut robots = vec![0; blueprint.resources.len()];
+        robots[blueprint.starting_robot()] = 1;
         Self {
             elapsed_minutes: 0,
-            ores: 0,
-            clays: 0,
-            obsidians: 0,
-            geodes: 0,
-            orebots: 1,
-            claybots: 0,
-            obsidianbots: 0,
-            geodebots: 0,
+            resources: vec![0; blueprint.resources.len()],
+            robots,
         }
     }
 
     fn tick(&mut self, minutes: isize) {
         self.elapsed_minutes += minutes;
-        self.ores += self.orebots * minutes;
-        self.clays += self.claybots * minutes;
-        self.obsidians += self.obsidianbots * minutes;
-        self.geodes += self.geodebots * minutes;
+        for (amount, robots) in self.resources.iter_mut().zip(&self.robots) {
+            *amount += robots * minutes;
+        }
     }
 
-    fn build_orebot(&mut self, blueprint: &Blueprint) {
-        self.ores -= blueprint.ore_for_orebot;
-        self.orebots += 1;
+    fn build(&mut self, recipe: &Recipe) {
+        for &(resource, amount) in &recipe.costs {
+            self.resources[resource] -= amount;
+        }
+        self.robots[recipe.robot] += 1;
     }
 
-    fn build_claybot(&mut self, blueprint: &Blueprint) {
-        self.ores -= blueprint.ore_for_claybot;
-        self.claybots += 1;
+    // minutes of collecting needed before the recipe is affordable, if it ever is
+    fn minutes_until_affordable(&self, recipe: &Recipe) -> Option<isize> {
+        recipe
+            .costs
+            .iter()
+            .map(|&(resource, amount)| {
+                let missing = amount - self.resources[resource];
+                if missing <= 0 {
+                    Some(0)
+                } else if self.robots[resource] == 0 {
+                    None
+                } else {
+                    Some(div_ceil(missing, self.robots[resource]))
+                }
+            })
+            .try_fold(0, |most, minutes| Some(most.max(minutes?)))
     }
 
-    fn build_obsidianbot(&mut self, blueprint: &Blueprint) {
-        self.ores -= blueprint.ore_for_obsidianbot;
-        self.clays -= blueprint.clay_for_obsidianbot;
-        self.obsidianbots += 1;
+    /// build a new goal robot every minute until the end
+    fn maximum_possible(&self, goal: usize, minutes: isize) -> isize {
+        let minutes_left = minutes - self.elapsed_minutes;
+        self.resources[goal]
+            + (0..minutes_left)
+                .map(|t| t + self.robots[goal])
+                .sum::<isize>()
     }
 
-    fn build_geodebot(&mut self, blueprint: &Blueprint) {
-        self.ores -= blueprint.ore_for_geodebot;
-        self.obsidians -= blueprint.obsidian_for_geodebot;
-        self.geodebots += 1;
+    fn projected(&self, goal: usize, minutes: isize) -> isize {
+        self.resources[goal] + (minutes - self.elapsed_minutes) * self.robots[goal]
     }
+}
 
-    /// build a new geodebot every minute until the end
-    fn maximum_possible_geodes(&self, minutes: isize) -> isize {
-        let minutes_left = minutes - self.elapsed_minutes;
-        self.geodes + (0..minutes_left).map(|t| t + self.geodebots).sum::<isize>()
-    }
+// robots built so far, newest first, shared between the states that branch off from each other
+struct Build {
+    minute: isize,
+    recipe: usize,
+    previous: Option<Rc<Build>>,
+}
 
-    fn projected_geodes(&self, minutes: isize) -> isize {
-        self.geodes + (minutes - self.elapsed_minutes) * self.geodebots
-    }
+// the most of the blueprint's last resource that can be collected in time, and how
+pub fn best_schedule(blueprint: &Blueprint, minutes: isize) -> Schedule {
+    search(blueprint, minutes, None).unwrap()
 }
 
-fn max_geodes(state: State, blueprint: &Blueprint, minutes: isize) -> isize {
-    let mut queue: Vec<State> = Vec::new();
-    let mut max_geodes = 0;
-    queue.push(state);
+// gives up with `None` once the deadline has passed
+fn search(blueprint: &Blueprint, minutes: isize, deadline: Option<Instant>) -> Option<Schedule> {
+    let goal = blueprint.goal();
+    let state = State::new(blueprint);
 
-    let max_orebots = blueprint
-        .ore_for_orebot
-        .max(blueprint.ore_for_claybot)
-        .max(blueprint.ore_for_obsidianbot)
-        .max(blueprint.ore_for_geodebot);
-    let max_claybots = blueprint.clay_for_obsidianbot;
-    let max_obsidianbots = blueprint.obsidian_for_geodebot;
+    let mut queue: Vec<(State, Option<Rc<Build>>)> = Vec::new();
+    let mut max_collected = 0;
+    let mut best_builds = None;
+    queue.push((state.clone(), None));
 
-    while let Some(state) = queue.pop() {
-        if state.maximum_possible_geodes(minutes) < max_geodes {
+    // there's no use in collecting more of a resource per minute than a robot can cost, since
+    // only one robot gets built each minute
+    let max_robots: Vec<isize> = (0..blueprint.resources.len())
+        .map(|resource| {
+            if resource == goal {
+                return isize::MAX;
+            }
+            blueprint
+                .recipes
+                .iter()
+                .flat_map(|recipe| &recipe.costs)
+                .filter(|&&(cost, _)| cost == resource)
+                .map(|&(_, amount)| amount)
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut iterations: usize = 0;
+    while let Some((state, builds)) = queue.pop() {
+        // checking the clock is slow compared to expanding a state
+        if iterations.is_multiple_of(4096)
+            && deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return None;
+        }
+        iterations += 1;
+
+        if state.maximum_possible(goal, minutes) < max_collected {
             // no point in pursuing this state further
             continue;
         }
 
-        max_geodes = max_geodes.max(state.projected_geodes(minutes));
+        if state.projected(goal, minutes) > max_collected || best_builds.is_none() {
+            max_collected = state.projected(goal, minutes);
+            best_builds = Some(builds.clone());
+        }
 
-        // build orebot
-        if state.orebots < max_orebots {
-            let mut state = state;
-            let minutes_left =
-                div_ceil(blueprint.ore_for_orebot - state.ores, state.orebots).max(0);
-            state.tick(minutes_left + 1);
-            state.build_orebot(blueprint);
-            if state.elapsed_minutes < minutes {
-                queue.push(state);
+        for (index, recipe) in blueprint.recipes.iter().enumerate() {
+            if state.robots[recipe.robot] >= max_robots[recipe.robot] {
+                continue;
             }
-        }
+            let Some(minutes_left) = state.minutes_until_affordable(recipe) else {
+                continue;
+            };
 
-        // build claybot
-        if state.claybots < max_claybots {
-            let mut state = state;
-            let minutes_left =
-                div_ceil(blueprint.ore_for_claybot - state.ores, state.orebots).max(0);
+            let mut state = state.clone();
             state.tick(minutes_left + 1);
-            state.build_claybot(blueprint);
+            state.build(recipe);
             if state.elapsed_minutes < minutes {
-                queue.push(state);
+                let build = Build {
+                    minute: state.elapsed_minutes,
+                    recipe: index,
+                    previous: builds.clone(),
+                };
+                queue.push((state, Some(Rc::new(build))));
             }
         }
+    }
 
-        // build obsidianbots
-        if state.obsidianbots < max_obsidianbots && state.claybots > 0 {
-            let mut state = state;
-            let minutes_until_ore =
-                div_ceil(blueprint.ore_for_obsidianbot - state.ores, state.orebots);
-            let minutes_until_clay =
-                div_ceil(blueprint.clay_for_obsidianbot - state.clays, state.claybots);
-            let minutes_left = minutes_until_ore.max(minutes_until_clay).max(0);
-            state.tick(minutes_left + 1);
-            state.build_obsidianbot(blueprint);
-            if state.elapsed_minutes < minutes {
-                queue.push(state);
-            }
+    let mut recipes = Vec::new();
+    let mut build = best_builds.flatten();
+    while let Some(current) = build.take() {
+        recipes.push((current.minute, current.recipe));
+        build.clone_from(&current.previous);
+    }
+    recipes.reverse();
+
+    Some(Schedule::replay(state, blueprint, minutes, &recipes))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    Started {
+        blueprint_id: isize,
+    },
+    Finished {
+        blueprint_id: isize,
+        collected: isize,
+        elapsed: Duration,
+    },
+    OutOfTime {
+        blueprint_id: isize,
+        elapsed: Duration,
+    },
+}
+
+// searches blueprints on several threads at once, all sharing one time budget
+pub struct BlueprintEvaluator {
+    minutes: isize,
+    threads: usize,
+    budget: Option<Duration>,
+}
+
+impl BlueprintEvaluator {
+    pub fn new(minutes: isize) -> Self {
+        Self {
+            minutes,
+            threads: thread::available_parallelism().map_or(1, NonZero::get),
+            budget: None,
         }
+    }
 
-        // build geodebots
-        if state.obsidianbots > 0 {
-            let mut state = state;
-            let minutes_until_ore =
-                div_ceil(blueprint.ore_for_geodebot - state.ores, state.orebots);
-            let minutes_until_obsidian = div_ceil(
-                blueprint.obsidian_for_geodebot - state.obsidians,
-                state.obsidianbots,
-            );
-            let minutes_left = minutes_until_ore.max(minutes_until_obsidian).max(0);
-            state.tick(minutes_left + 1);
-            state.build_geodebot(blueprint);
-            if state.elapsed_minutes < minutes {
-                queue.push(state);
+    #[must_use]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn with_budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    // The schedules in the same order as the blueprints, or `None` for blueprints that weren't
+    // finished within the budget. `report` is called from the worker threads as each blueprint
+    // starts and ends.
+    pub fn evaluate(
+        &self,
+        blueprints: &[Blueprint],
+        report: impl Fn(Progress) + Sync,
+    ) -> Vec<Option<Schedule>> {
+        let deadline = self.budget.map(|budget| Instant::now() + budget);
+        let next_blueprint = AtomicUsize::new(0);
+        let schedules = Mutex::new(vec![None; blueprints.len()]);
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(blueprints.len()) {
+                scope.spawn(|| loop {
+                    let index = next_blueprint.fetch_add(1, Ordering::Relaxed);
+                    let Some(blueprint) = blueprints.get(index) else {
+                        break;
+                    };
+
+                    let start = Instant::now();
+                    report(Progress::Started {
+                        blueprint_id: blueprint.id,
+                    });
+
+                    let schedule = search(blueprint, self.minutes, deadline);
+
+                    let elapsed = start.elapsed();
+                    report(match &schedule {
+                        Some(schedule) => Progress::Finished {
+                            blueprint_id: blueprint.id,
+                            collected: schedule.collected,
+                            elapsed,
+                        },
+                        None => Progress::OutOfTime {
+                            blueprint_id: blueprint.id,
+                            elapsed,
+                        },
+                    });
+
+                    schedules.lock().unwrap()[index] = schedule;
+                });
+            }
+        });
+
+        schedules.into_inner().unwrap()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Minute {
+    pub minute: isize,
+    // the resource collected by the robot built this minute
+    pub built: Option<usize>,
+    pub state: State,
+}
+
+// the winning build order of a blueprint, minute by minute
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub blueprint_id: isize,
+    pub resources: Vec<String>,
+    pub goal: usize,
+    pub collected: isize,
+    pub minutes: Vec<Minute>,
+}
+
+impl Schedule {
+    // robots are paid for at the start of `minute` and start collecting once it ends
+    fn replay(
+        mut state: State,
+        blueprint: &Blueprint,
+        minutes: isize,
+        recipes: &[(isize, usize)],
+    ) -> Self {
+        let mut recipes = recipes.iter().peekable();
+        let mut schedule = Vec::new();
+
+        while state.elapsed_minutes < minutes {
+            state.tick(1);
+            let recipe = recipes
+                .next_if(|(minute, _)| *minute == state.elapsed_minutes)
+                .map(|&(_, recipe)| &blueprint.recipes[recipe]);
+            if let Some(recipe) = recipe {
+                state.build(recipe);
             }
+            schedule.push(Minute {
+                minute: state.elapsed_minutes,
+                built: recipe.map(|recipe| recipe.robot),
+                state: state.clone(),
+            });
+        }
+
+        Schedule {
+            blueprint_id: blueprint.id,
+            resources: blueprint.resources.clone(),
+            goal: blueprint.goal(),
+            collected: state.resources[blueprint.goal()],
+            minutes: schedule,
         }
     }
 
-    max_geodes
+    pub fn resource(&self, name: &str) -> Option<usize> {
+        self.resources.iter().position(|resource| resource == name)
+    }
+
+    pub fn quality_level(&self) -> isize {
+        self.blueprint_id * self.collected
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "blueprint {}: {} {}",
+            self.blueprint_id, self.collected, self.resources[self.goal]
+        )?;
+
+        for minute in &self.minutes {
+            let built = match minute.built {
+                Some(robot) => format!("build {}-collecting robot", self.resources[robot]),
+                None => "wait".to_string(),
+            };
+            let inventory: Vec<String> = self
+                .resources
+                .iter()
+                .zip(&minute.state.resources)
+                .zip(&minute.state.robots)
+                .map(|((name, amount), robots)| format!("{amount} {name} ({robots} collecting)"))
+                .collect();
+            writeln!(
+                f,
+                "minute {}: {built}, then {}",
+                minute.minute,
+                inventory.join(", ")
+            )?;
+        }
+        Ok(())
+    }
 }
 
 fn div_ceil(a: isize, b: isize) -> isize {
@@ -206,27 +512,44 @@ fn div_ceil(a: isize, b: isize) -> isize {
 }
 
 pub fn sum_quality_levels(filename: &str) -> isize {
-    fs::read_to_string(filename)
-        .unwrap()
-        .lines()
-        .map(Blueprint::new)
-        .map(|blueprint| max_geodes(State::new(), &blueprint, 24) * blueprint.id)
+    explain_quality_levels(filename)
+        .iter()
+        .map(Schedule::quality_level)
         .sum()
 }
 
+pub fn explain_quality_levels(filename: &str) -> Vec<Schedule> {
+    let blueprints = parse_blueprints(filename);
+    BlueprintEvaluator::new(24)
+        .evaluate(&blueprints, |_| {})
+        .into_iter()
+        .map(Option::unwrap)
+        .collect()
+}
+
 pub fn multiply_first_three_blueprints(filename: &str) -> isize {
-    fs::read_to_string(filename)
-        .unwrap()
-        .lines()
-        .take(3)
-        .map(Blueprint::new)
-        .map(|blueprint| max_geodes(State::new(), &blueprint, 32))
+    explain_first_three_blueprints(filename)
+        .iter()
+        .map(|schedule| schedule.collected)
         .product()
 }
 
+pub fn explain_first_three_blueprints(filename: &str) -> Vec<Schedule> {
+    let mut blueprints = parse_blueprints(filename);
+    blueprints.truncate(3);
+    BlueprintEvaluator::new(32)
+        .evaluate(&blueprints, |_| {})
+        .into_iter()
+        .map(Option::unwrap)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{day19, fetch_input};
+    use std::{sync::Mutex, time::Duration};
+
+    use crate::day19::{self, Blueprint, BlueprintEvaluator, Progress, Recipe};
+    use crate::fetch_input;
 
     #[test]
     fn sum_quality_levels() {
@@ -249,4 +572,110 @@ mod tests {
             assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
+
+    #[test]
+    fn explain_quality_levels() {
+        let schedules = day19::explain_quality_levels("example/day19.txt");
+        let got: Vec<isize> = schedules.iter().map(|s| s.collected).collect();
+        assert_eq!(got, vec![9, 12]);
+
+        let schedule = &schedules[0];
+        assert_eq!(schedule.minutes.len(), 24);
+        assert_eq!(schedule.minutes[2].built, schedule.resource("clay"));
+        assert_eq!(schedule.minutes[2].state.resources[0], 1);
+        let last = schedule.minutes.last().unwrap();
+        assert_eq!(last.state.resources[3], schedule.collected);
+    }
+
+    #[test]
+    fn parse_blueprint_list() {
+        let input = "\
+Blueprint 7: Each ore robot costs 2 ore. Each crystal robot costs 1 ore, 2 clay and 3 sand.
+Blueprint 8 Each ore robot costs 2 ore.
+";
+        let got = day19::parse_blueprint_list(input);
+        let error = got.unwrap_err();
+        assert_eq!(error.line_number, 2);
+        assert_eq!(error.reason, "missing ':' after the id");
+
+        let got = day19::parse_blueprint_list(input.lines().next().unwrap()).unwrap();
+        let want = vec![Blueprint {
+            id: 7,
+            resources: ["ore", "crystal", "clay", "sand"]
+                .map(String::from)
+                .to_vec(),
+            recipes: vec![
+                Recipe {
+                    robot: 0,
+                    costs: vec![(0, 2)],
+                },
+                Recipe {
+                    robot: 1,
+                    costs: vec![(0, 1), (2, 2), (3, 3)],
+                },
+            ],
+        }];
+        assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+    }
+
+    #[test]
+    fn best_schedule_with_other_resources() {
+        let input = "Blueprint 1: Each ore robot costs 3 ore. Each sand robot costs 1 ore. \
+            Each glass robot costs 2 ore and 2 sand. Each lens robot costs 1 sand and 1 glass.";
+        let blueprint = &day19::parse_blueprint_list(input).unwrap()[0];
+
+        // the first lens robot is ready after minute 7, and another one follows every minute
+        let tests = vec![(5, 0), (8, 1), (12, 15)];
+
+        for test in tests {
+            let (minutes, want) = test;
+            let schedule = day19::best_schedule(blueprint, minutes);
+            let got = schedule.collected;
+            assert_eq!(got, want, "got {got}, wanted {want}, for {minutes} minutes");
+        }
+    }
+
+    #[test]
+    fn blueprint_evaluator() {
+        let input = std::fs::read_to_string("example/day19.txt").unwrap();
+        let blueprints = day19::parse_blueprint_list(&input).unwrap();
+
+        let progress = Mutex::new(Vec::new());
+        let schedules = BlueprintEvaluator::new(24)
+            .with_threads(2)
+            .evaluate(&blueprints, |update| progress.lock().unwrap().push(update));
+
+        let got: Vec<Option<isize>> = schedules
+            .iter()
+            .map(|schedule| schedule.as_ref().map(|schedule| schedule.collected))
+            .collect();
+        assert_eq!(got, vec![Some(9), Some(12)]);
+
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress.len(), 4);
+        assert!(progress.contains(&Progress::Started { blueprint_id: 2 }));
+        assert!(progress.iter().any(|update| matches!(
+            update,
+            Progress::Finished {
+                blueprint_id: 1,
+                collected: 9,
+                ..
+            }
+        )));
+
+        // nothing can be finished without any time
+        let progress = Mutex::new(Vec::new());
+        let schedules = BlueprintEvaluator::new(24)
+            .with_budget(Duration::ZERO)
+            .evaluate(&blueprints, |update| progress.lock().unwrap().push(update));
+
+        assert!(schedules.iter().all(Option::is_none));
+        let timed_out = progress
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .filter(|update| matches!(update, Progress::OutOfTime { .. }))
+            .count();
+        assert_eq!(timed_out, 2);
+    }
 }
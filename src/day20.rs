@@ -1,93 +1,210 @@
-use std::{collections::VecDeque, fs};
+use std::fs;
 
 const DECRYPTION_KEY: isize = 811_589_153;
 
-struct Number {
-    value: isize,
-    id: usize,
+// how to decrypt: multiply every number by `key`, mix `rounds` times, then read the numbers
+// `offsets` places after the `anchor` value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixConfig {
+    pub key: isize,
+    pub rounds: usize,
+    pub offsets: Vec<usize>,
+    pub anchor: isize,
 }
 
-impl Number {
-    fn new(value: isize, id: usize) -> Self {
-        Self { value, id }
+impl Default for MixConfig {
+    fn default() -> Self {
+        Self {
+            key: 1,
+            rounds: 1,
+            offsets: vec![1000, 2000, 3000],
+            anchor: 0,
+        }
+    }
+}
+
+impl MixConfig {
+    pub fn with_decryption_key() -> Self {
+        Self {
+            key: DECRYPTION_KEY,
+            rounds: 10,
+            ..Self::default()
+        }
     }
 }
 
-struct Numbers(VecDeque<Number>);
+// The numbers in their current order, split into blocks of about √n. Every number remembers its
+// block, so finding a number and moving it only scans the block lists and two blocks, instead of
+// the whole list. Numbers are identified by their position in the original file.
+struct Numbers {
+    values: Vec<isize>,
+    blocks: Vec<Vec<usize>>,
+    block_of: Vec<usize>,
+    block_size: usize,
+}
 
 impl Numbers {
     fn from_file(filename: &str) -> Self {
-        let numbers_list = fs::read_to_string(filename)
+        let values = fs::read_to_string(filename)
             .unwrap()
             .lines()
-            .enumerate()
-            .map(|(id, line)| Number::new(line.parse().unwrap(), id))
+            .map(|line| line.parse().unwrap())
             .collect();
 
-        Self(numbers_list)
+        Self::new(values)
+    }
+
+    fn new(values: Vec<isize>) -> Self {
+        let block_size = values.len().isqrt().max(1);
+        let mut numbers = Self {
+            blocks: vec![(0..values.len()).collect()],
+            block_of: vec![0; values.len()],
+            values,
+            block_size,
+        };
+        numbers.rebalance();
+        numbers
+    }
+
+    // splits the order back into evenly sized blocks, once one of them has grown too big
+    fn rebalance(&mut self) {
+        let order: Vec<usize> = self.blocks.concat();
+        self.blocks = order
+            .chunks(self.block_size)
+            .map(<[usize]>::to_vec)
+            .collect();
+
+        for (block, ids) in self.blocks.iter().enumerate() {
+            for &id in ids {
+                self.block_of[id] = block;
+            }
+        }
+    }
+
+    fn remove(&mut self, id: usize) -> usize {
+        let block = self.block_of[id];
+        let offset = self.blocks[block].iter().position(|&n| n == id).unwrap();
+        self.blocks[block].remove(offset);
+
+        self.blocks[..block].iter().map(Vec::len).sum::<usize>() + offset
+    }
+
+    fn insert(&mut self, index: usize, id: usize) {
+        let mut offset = index;
+        let mut block = 0;
+        while block + 1 < self.blocks.len() && offset > self.blocks[block].len() {
+            offset -= self.blocks[block].len();
+            block += 1;
+        }
+
+        self.blocks[block].insert(offset, id);
+        self.block_of[id] = block;
+
+        if self.blocks[block].len() > 2 * self.block_size {
+            self.rebalance();
+        }
     }
 
     // this preserves the relative ordering of items in the list
     // but does not preserve the absolute indexes of the items.
     // It doesn't matter since the solution is based on values relative to
     // zero.
-    fn shift_item(&mut self, index: usize) {
-        let item = self.0.remove(index).unwrap();
-        let delta = isize::try_from(index).unwrap() + item.value;
-        let new_index = delta.rem_euclid(isize::try_from(self.0.len()).unwrap());
-        self.0.insert(usize::try_from(new_index).unwrap(), item);
-    }
-
-    // this is the bottleneck - it takes O(n) to find
-    // the next index to shift.
-    fn next_index_to_shift(&self, id: usize) -> usize {
-        self.0.iter().position(|n| n.id == id).unwrap()
+    fn shift_item(&mut self, id: usize) {
+        let index = self.remove(id);
+        let delta = isize::try_from(index).unwrap() + self.values[id];
+        let new_index = delta.rem_euclid(isize::try_from(self.values.len() - 1).unwrap());
+        self.insert(usize::try_from(new_index).unwrap(), id);
     }
 
     fn mix(&mut self) {
-        for i in 0..self.0.len() {
-            let index = self.next_index_to_shift(i);
-            self.shift_item(index);
+        if self.values.len() < 2 {
+            return;
+        }
+        for id in 0..self.values.len() {
+            self.shift_item(id);
         }
     }
 
-    fn coordinates(&self) -> [isize; 3] {
-        let zero_position = self.0.iter().position(|n| n.value == 0).unwrap();
-        let n_numbers = self.0.len();
-        let x = (zero_position + 1000) % n_numbers;
-        let y = (zero_position + 2000) % n_numbers;
-        let z = (zero_position + 3000) % n_numbers;
-        [self.0[x].value, self.0[y].value, self.0[z].value]
+    fn sequence(&self) -> Vec<isize> {
+        self.blocks
+            .iter()
+            .flatten()
+            .map(|&id| self.values[id])
+            .collect()
+    }
+
+    // the list is circular, so it's read starting from the anchor
+    // `None` when the anchor isn't in the list
+    fn sequence_from(&self, anchor: isize) -> Option<Vec<isize>> {
+        let mut sequence = self.sequence();
+        let anchor_position = sequence.iter().position(|&n| n == anchor)?;
+        sequence.rotate_left(anchor_position);
+        Some(sequence)
+    }
+
+    fn coordinates(&self, offsets: &[usize], anchor: isize) -> Option<Vec<isize>> {
+        // the anchor is in the list, so it isn't empty
+        let sequence = self.sequence_from(anchor)?;
+        Some(
+            offsets
+                .iter()
+                .map(|offset| sequence[offset % sequence.len()])
+                .collect(),
+        )
     }
 
     fn apply_decryption_key(&mut self, decryption_key: isize) {
-        self.0 = self
-            .0
-            .iter()
-            .map(|number| Number::new(number.value * decryption_key, number.id))
-            .collect();
+        for value in &mut self.values {
+            *value *= decryption_key;
+        }
     }
 }
 
 pub fn mix_once(filename: &str) -> isize {
-    let mut numbers = Numbers::from_file(filename);
-    numbers.mix();
-    numbers.coordinates().iter().sum()
+    grove_coordinates(filename, &MixConfig::default())
+        .unwrap()
+        .iter()
+        .sum()
 }
 
 pub fn mix_ten_times(filename: &str) -> isize {
+    grove_coordinates(filename, &MixConfig::with_decryption_key())
+        .unwrap()
+        .iter()
+        .sum()
+}
+
+// `None` when the anchor, after applying the key, isn't one of the numbers
+pub fn grove_coordinates(filename: &str, config: &MixConfig) -> Option<Vec<isize>> {
     let mut numbers = Numbers::from_file(filename);
-    numbers.apply_decryption_key(DECRYPTION_KEY);
+    numbers.apply_decryption_key(config.key);
 
-    for _ in 0..10 {
+    for _ in 0..config.rounds {
         numbers.mix();
     }
 
-    numbers.coordinates().iter().sum()
+    numbers.coordinates(&config.offsets, config.anchor)
+}
+
+// the whole list after every round, each starting from the anchor. `None` when the anchor,
+// after applying the key, isn't one of the numbers
+pub fn mix_rounds(filename: &str, config: &MixConfig) -> Option<Vec<Vec<isize>>> {
+    let mut numbers = Numbers::from_file(filename);
+    numbers.apply_decryption_key(config.key);
+
+    (0..config.rounds)
+        .map(|_| {
+            numbers.mix();
+            numbers.sequence_from(config.anchor)
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::VecDeque;
+
+    use crate::day20::{MixConfig, Numbers};
     use crate::{day20, fetch_input};
 
     #[test]
@@ -107,8 +224,8 @@ mod tests {
         fetch_input(20);
 
         let tests = vec![
-            ("example/day20.txt", 1623178306),
-            ("input/day20.txt", 5382459262696),
+            ("example/day20.txt", 1_623_178_306),
+            ("input/day20.txt", 5_382_459_262_696),
         ];
 
         for (infile, want) in tests {
@@ -116,4 +233,85 @@ mod tests {
             assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
+
+    #[test]
+    fn mix_rounds() {
+        let config = MixConfig {
+            rounds: 2,
+            ..MixConfig::with_decryption_key()
+        };
+
+        let got = day20::mix_rounds("example/day20.txt", &config);
+        let want = Some(vec![
+            vec![
+                0,
+                -2_434_767_459,
+                3_246_356_612,
+                -1_623_178_306,
+                2_434_767_459,
+                1_623_178_306,
+                811_589_153,
+            ],
+            vec![
+                0,
+                2_434_767_459,
+                1_623_178_306,
+                3_246_356_612,
+                -2_434_767_459,
+                -1_623_178_306,
+                811_589_153,
+            ],
+        ]);
+        assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+
+        let config = MixConfig {
+            offsets: vec![1, 2, 7],
+            anchor: 4,
+            ..MixConfig::default()
+        };
+        let got = day20::grove_coordinates("example/day20.txt", &config);
+        assert_eq!(got, Some(vec![0, 3, 4]));
+
+        // the key is applied before looking for the anchor
+        let config = MixConfig {
+            anchor: 4,
+            ..MixConfig::with_decryption_key()
+        };
+        assert_eq!(day20::grove_coordinates("example/day20.txt", &config), None);
+        assert_eq!(day20::mix_rounds("example/day20.txt", &config), None);
+    }
+
+    #[test]
+    fn mix_matches_list_shifting() {
+        // pseudo-random values, with a zero somewhere in the middle
+        let mut seed: isize = 12345;
+        let values: Vec<isize> = (0..3000)
+            .map(|i| {
+                seed = (seed * 1_103_515_245 + 12345).rem_euclid(1 << 31);
+                if i == 1500 {
+                    0
+                } else {
+                    seed % 20_000 - 10_000
+                }
+            })
+            .collect();
+
+        let mut numbers = Numbers::new(values.clone());
+        numbers.mix();
+        numbers.mix();
+
+        let mut list: VecDeque<(usize, isize)> = values.into_iter().enumerate().collect();
+        for _ in 0..2 {
+            for id in 0..list.len() {
+                let index = list.iter().position(|&(n, _)| n == id).unwrap();
+                let (id, value) = list.remove(index).unwrap();
+                let new_index = (isize::try_from(index).unwrap() + value)
+                    .rem_euclid(isize::try_from(list.len()).unwrap());
+                list.insert(usize::try_from(new_index).unwrap(), (id, value));
+            }
+        }
+        let want: Vec<isize> = list.into_iter().map(|(_, value)| value).collect();
+
+        assert_eq!(numbers.sequence(), want);
+    }
 }
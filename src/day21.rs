@@ -1,4 +1,10 @@
-use std::{collections::HashMap, fs};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{self, Write},
+    fs,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
 
 type ExpressionTable = HashMap<String, Yell>;
 
@@ -28,7 +34,7 @@ impl From<String> for Yell {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum Operator {
     Plus,
     Minus,
@@ -48,10 +54,28 @@ impl<'a> From<&'a str> for Operator {
     }
 }
 
+impl Operator {
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Operator::Plus | Operator::Minus => 1,
+            Operator::Multiply | Operator::Divide => 2,
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Node {
-    Number(usize),
-    Variable,
+    Number(Rational),
+    Variable(String),
     Expression {
         left: Box<Node>,
         operator: Operator,
@@ -59,55 +83,507 @@ enum Node {
     },
 }
 
+impl Node {
+    // how tightly the node binds when written out. a fraction is written as a division, and a
+    // negative number always gets parentheses
+    fn precedence(&self) -> u8 {
+        match self {
+            Node::Number(n) if n.numerator() < 0 => 0,
+            Node::Number(n) if n.denominator() != 1 => 2,
+            Node::Number(_) | Node::Variable(_) => 3,
+            Node::Expression { operator, .. } => operator.precedence(),
+        }
+    }
+}
+
+// infix, with only the parentheses needed to keep the meaning
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Number(n) => write!(f, "{n}"),
+            Node::Variable(name) => write!(f, "{name}"),
+            Node::Expression {
+                left,
+                operator,
+                right,
+            } => {
+                let precedence = operator.precedence();
+
+                if left.precedence() < precedence {
+                    write!(f, "({left})")?;
+                } else {
+                    write!(f, "{left}")?;
+                }
+
+                write!(f, " {} ", operator.symbol())?;
+
+                // subtraction and division don't regroup, so a - (b - c) keeps its parentheses
+                let regroups = matches!(operator, Operator::Plus | Operator::Multiply);
+                if right.precedence() < precedence
+                    || (right.precedence() == precedence && !regroups)
+                {
+                    write!(f, "({right})")
+                } else {
+                    write!(f, "{right}")
+                }
+            }
+        }
+    }
+}
+
+// an exact fraction, always stored in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational {
+        numerator: 0,
+        denominator: 1,
+    };
+
+    pub const ONE: Rational = Rational {
+        numerator: 1,
+        denominator: 1,
+    };
+
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        assert!(denominator != 0, "zero denominator");
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        Rational {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    pub fn to_integer(&self) -> Option<i128> {
+        (self.denominator == 1).then_some(self.numerator)
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl From<i128> for Rational {
+    fn from(value: i128) -> Self {
+        Rational::new(value, 1)
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, other: Rational) -> Rational {
+        Rational::new(
+            self.numerator * other.denominator + other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+
+    fn sub(self, other: Rational) -> Rational {
+        self + -other
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational {
+            numerator: -self.numerator,
+            denominator: self.denominator,
+        }
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, other: Rational) -> Rational {
+        Rational::new(
+            self.numerator * other.numerator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+// panics when dividing by zero, like integer division
+impl Div for Rational {
+    type Output = Rational;
+
+    fn div(self, other: Rational) -> Rational {
+        Rational::new(
+            self.numerator * other.denominator,
+            self.denominator * other.numerator,
+        )
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+// a·x + b·y + … + c, without any zero coefficients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Linear {
+    pub coefficients: BTreeMap<String, Rational>,
+    pub constant: Rational,
+}
+
+impl Linear {
+    pub fn constant(value: Rational) -> Self {
+        Linear {
+            coefficients: BTreeMap::new(),
+            constant: value,
+        }
+    }
+
+    pub fn variable(name: &str) -> Self {
+        Linear {
+            coefficients: BTreeMap::from([(name.to_string(), Rational::ONE)]),
+            constant: Rational::ZERO,
+        }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    pub fn coefficient(&self, name: &str) -> Rational {
+        self.coefficients
+            .get(name)
+            .copied()
+            .unwrap_or(Rational::ZERO)
+    }
+
+    #[must_use]
+    pub fn plus(&self, other: &Linear) -> Linear {
+        let mut result = self.clone();
+        for (name, coefficient) in &other.coefficients {
+            let sum = result.coefficient(name) + *coefficient;
+            if sum.is_zero() {
+                result.coefficients.remove(name);
+            } else {
+                result.coefficients.insert(name.clone(), sum);
+            }
+        }
+        result.constant = result.constant + other.constant;
+        result
+    }
+
+    #[must_use]
+    pub fn minus(&self, other: &Linear) -> Linear {
+        self.plus(&other.scale(-Rational::ONE))
+    }
+
+    #[must_use]
+    pub fn scale(&self, factor: Rational) -> Linear {
+        if factor.is_zero() {
+            return Linear::constant(Rational::ZERO);
+        }
+        Linear {
+            coefficients: self
+                .coefficients
+                .iter()
+                .map(|(name, coefficient)| (name.clone(), *coefficient * factor))
+                .collect(),
+            constant: self.constant * factor,
+        }
+    }
+
+    /// The value of `variable` that makes this expression zero, in terms of the other
+    /// variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NoUniqueSolution` when `variable` doesn't appear in the expression.
+    pub fn solve_for(&self, variable: &str) -> Result<Linear, SolveError> {
+        let coefficient = self.coefficient(variable);
+        if coefficient.is_zero() {
+            return Err(SolveError::NoUniqueSolution {
+                variable: variable.to_string(),
+            });
+        }
+
+        let mut rest = self.clone();
+        rest.coefficients.remove(variable);
+        Ok(rest.scale(-Rational::ONE / coefficient))
+    }
+
+    /// The value of an expression without variables, as an integer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Unresolved` while the expression still has variables, and `NonInteger` when
+    /// its value is a fraction.
+    pub fn to_integer(&self) -> Result<i128, SolveError> {
+        if !self.is_constant() {
+            return Err(SolveError::Unresolved(self.clone()));
+        }
+        self.constant
+            .to_integer()
+            .ok_or(SolveError::NonInteger(self.constant))
+    }
+}
+
+impl fmt::Display for Linear {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms: Vec<(Rational, Option<&str>)> = self
+            .coefficients
+            .iter()
+            .map(|(name, coefficient)| (*coefficient, Some(name.as_str())))
+            .collect();
+        if !self.constant.is_zero() || terms.is_empty() {
+            terms.push((self.constant, None));
+        }
+
+        for (index, (value, name)) in terms.into_iter().enumerate() {
+            let value = match (index, value.numerator() < 0) {
+                (0, _) => value,
+                (_, true) => {
+                    write!(f, " - ")?;
+                    -value
+                }
+                (_, false) => {
+                    write!(f, " + ")?;
+                    value
+                }
+            };
+            match name {
+                Some(name) if value == Rational::ONE => write!(f, "{name}")?,
+                Some(name) => write!(f, "{value}·{name}")?,
+                None => write!(f, "{value}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    // unknowns multiplied together, or an unknown in a divisor
+    NonLinear,
+    DivisionByZero,
+    // the unknown cancels out of the equation
+    NoUniqueSolution { variable: String },
+    NonInteger(Rational),
+    // still depends on other unknowns
+    Unresolved(Linear),
+    // found by `validate` before anything was evaluated
+    InvalidTable(Vec<TableProblem>),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NonLinear => write!(f, "expression is not linear in the unknowns"),
+            SolveError::DivisionByZero => write!(f, "division by zero"),
+            SolveError::NoUniqueSolution { variable } => {
+                write!(f, "{variable} has no unique solution")
+            }
+            SolveError::NonInteger(value) => write!(f, "{value} is not an integer"),
+            SolveError::Unresolved(value) => write!(f, "{value} still has unknowns"),
+            SolveError::InvalidTable(problems) => {
+                let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+                write!(f, "invalid expressions: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableProblem {
+    // `referenced_by` is `None` when `root` itself is missing
+    Undefined {
+        monkey: String,
+        referenced_by: Option<String>,
+    },
+    // starts and ends with the same monkey
+    Cycle {
+        path: Vec<String>,
+    },
+    Unused {
+        monkey: String,
+    },
+    // the monkey's divisor always comes out as zero
+    DivisionByZero {
+        monkey: String,
+    },
+}
+
+impl fmt::Display for TableProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableProblem::Undefined {
+                monkey,
+                referenced_by: Some(referenced_by),
+            } => write!(f, "{monkey} is not defined, but {referenced_by} uses it"),
+            TableProblem::Undefined {
+                monkey,
+                referenced_by: None,
+            } => write!(f, "{monkey} is not defined"),
+            TableProblem::Cycle { path } => write!(f, "cycle {}", path.join(" -> ")),
+            TableProblem::Unused { monkey } => write!(f, "{monkey} is never used"),
+            TableProblem::DivisionByZero { monkey } => write!(f, "{monkey} divides by zero"),
+        }
+    }
+}
+
 pub fn find_root_number(filename: &str) -> usize {
     let file = fs::read_to_string(filename).unwrap();
-    let mut expressions = parse_input(&file);
-    let tree = parse_tree(&mut expressions, "root");
-    evaluate_tree(&tree)
+    let tree = checked_tree(&file, &[]).unwrap_or_else(|error| panic!("{error}"));
+    let root = evaluate_tree(&tree).unwrap().to_integer().unwrap();
+    usize::try_from(root).unwrap()
 }
 
 pub fn find_human_number(filename: &str) -> usize {
     let file = fs::read_to_string(filename).unwrap();
-    let mut expressions = parse_input(&file);
-
-    expressions.insert("humn".to_string(), Yell::Variable);
+    let human = solve_expressions(&file, &["humn"], "humn")
+        .and_then(|human| human.to_integer())
+        .unwrap_or_else(|error| panic!("{error}"));
+    usize::try_from(human).unwrap()
+}
 
-    let tree = parse_tree(&mut expressions, "root");
-    let tree = prune_tree(tree);
+/// Treats the `unknowns` monkeys as variables and the two sides of `root` as equal, then gives
+/// `target` in terms of the other unknowns.
+///
+/// # Errors
+///
+/// Returns `InvalidTable` when the monkeys don't form a tree under `root`, and otherwise the
+/// reason the equation couldn't be solved for `target`.
+pub fn solve_for(filename: &str, unknowns: &[&str], target: &str) -> Result<Linear, SolveError> {
+    let file = fs::read_to_string(filename).unwrap();
+    solve_expressions(&file, unknowns, target)
+}
 
-    let (left_tree, right_tree) = match tree {
-        Node::Expression { left, right, .. } => (left, right),
-        Node::Number(_) | Node::Variable => panic!("expected expression"),
+fn solve_expressions(file: &str, unknowns: &[&str], target: &str) -> Result<Linear, SolveError> {
+    // a root that isn't an equation has nothing to solve
+    let Node::Expression { left, right, .. } = checked_tree(file, unknowns)? else {
+        return Err(SolveError::NoUniqueSolution {
+            variable: target.to_string(),
+        });
     };
 
-    let (number, variable_tree) = match (*left_tree, *right_tree) {
-        (
-            Node::Number(n),
-            Node::Expression {
-                left,
-                operator,
-                right,
-            },
-        )
-        | (
-            Node::Expression {
-                left,
-                operator,
-                right,
-            },
-            Node::Number(n),
-        ) => (
-            n,
-            Node::Expression {
-                left,
-                operator,
-                right,
-            },
-        ),
-        (_, _) => panic!("unexpected top level branches"),
+    let left = simplify(&prune_tree(*left)?)?;
+    let right = simplify(&prune_tree(*right)?)?;
+    left.minus(&right).solve_for(target)
+}
+
+/// The tree under `root` after folding every branch without an unknown into a number. With
+/// unknowns, the two sides of `root` are shown as an equation.
+///
+/// # Errors
+///
+/// Returns `InvalidTable` when the monkeys don't form a tree under `root`, and
+/// `DivisionByZero` when folding a branch divides by zero.
+pub fn format_tree(filename: &str, unknowns: &[&str]) -> Result<String, SolveError> {
+    let file = fs::read_to_string(filename).unwrap();
+    format_expressions(&file, unknowns)
+}
+
+fn format_expressions(file: &str, unknowns: &[&str]) -> Result<String, SolveError> {
+    match checked_tree(file, unknowns)? {
+        Node::Expression { left, right, .. } if !unknowns.is_empty() => {
+            Ok(format!("{} = {}", prune_tree(*left)?, prune_tree(*right)?))
+        }
+        tree => Ok(prune_tree(tree)?.to_string()),
+    }
+}
+
+// Graphviz DOT of which monkeys listen to which. The unknowns are shown as `?`, and every monkey
+// whose number depends on them is drawn in bold, since those are what pruning can't fold away.
+pub fn dependency_graph_dot(filename: &str, unknowns: &[&str]) -> String {
+    let file = fs::read_to_string(filename).unwrap();
+    dependency_graph(&file, unknowns)
+}
+
+fn dependency_graph(file: &str, unknowns: &[&str]) -> String {
+    let expressions = parse_with_unknowns(file, unknowns);
+
+    let mut names: Vec<&String> = expressions.keys().collect();
+    names.sort();
+
+    let mut depends_on_unknown = HashMap::new();
+    let mut dot = String::from("digraph monkeys {\n");
+
+    for name in &names {
+        let label = match &expressions[*name] {
+            Yell::Number(n) => n.to_string(),
+            Yell::Variable => "?".to_string(),
+            Yell::Expression { operator, .. } => operator.symbol().to_string(),
+        };
+        let style = if depends_on_variable(&expressions, name, &mut depends_on_unknown) {
+            " style=bold"
+        } else {
+            ""
+        };
+        writeln!(dot, "    {name} [label=\"{name}\\n{label}\"{style}];").unwrap();
+    }
+
+    for name in &names {
+        if let Yell::Expression { left, right, .. } = &expressions[*name] {
+            writeln!(dot, "    {name} -> {left};").unwrap();
+            writeln!(dot, "    {name} -> {right};").unwrap();
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+fn depends_on_variable(
+    expressions: &ExpressionTable,
+    name: &str,
+    memo: &mut HashMap<String, bool>,
+) -> bool {
+    if let Some(&depends) = memo.get(name) {
+        return depends;
+    }
+    // stops the search from going round a cycle forever
+    memo.insert(name.to_string(), false);
+
+    let depends = match expressions.get(name) {
+        Some(Yell::Variable) => true,
+        Some(Yell::Expression { left, right, .. }) => {
+            depends_on_variable(expressions, left, memo)
+                || depends_on_variable(expressions, right, memo)
+        }
+        Some(Yell::Number(_)) | None => false,
     };
 
-    solve_for_human_number(variable_tree, number)
+    memo.insert(name.to_string(), depends);
+    depends
 }
 
 fn parse_input(file: &str) -> ExpressionTable {
@@ -122,128 +598,252 @@ fn parse_input(file: &str) -> ExpressionTable {
         .collect()
 }
 
-fn parse_tree(expressions: &'_ mut ExpressionTable, key: &str) -> Node {
-    let value = expressions.remove(key).unwrap();
+// Problems that would stop `root` from being evaluated, plus monkeys that `root` never listens
+// to. Division by zero is only found when the divisor doesn't depend on an unknown.
+pub fn validate(filename: &str, unknowns: &[&str]) -> Vec<TableProblem> {
+    let file = fs::read_to_string(filename).unwrap();
+    validate_table(&parse_with_unknowns(&file, unknowns))
+}
+
+fn validate_table(expressions: &ExpressionTable) -> Vec<TableProblem> {
+    let mut problems = Vec::new();
+
+    // monkeys being visited map to false, and finished ones to true
+    let mut visited = HashMap::new();
+    if expressions.contains_key("root") {
+        visit(
+            expressions,
+            "root",
+            &mut visited,
+            &mut Vec::new(),
+            &mut problems,
+        );
+    } else {
+        problems.push(TableProblem::Undefined {
+            monkey: "root".to_string(),
+            referenced_by: None,
+        });
+    }
+
+    let mut unused: Vec<&String> = expressions
+        .keys()
+        .filter(|name| !visited.contains_key(name.as_str()))
+        .collect();
+    unused.sort();
+    problems.extend(unused.into_iter().map(|monkey| TableProblem::Unused {
+        monkey: monkey.clone(),
+    }));
+
+    let mut dividers: Vec<(&String, &String)> = visited
+        .keys()
+        .filter_map(|name| match &expressions[name] {
+            Yell::Expression {
+                operator: Operator::Divide,
+                right,
+                ..
+            } => Some((name, right)),
+            _ => None,
+        })
+        .collect();
+    dividers.sort();
+
+    let mut values = HashMap::new();
+    for (monkey, divisor) in dividers {
+        if constant_value(expressions, divisor, &mut values).is_some_and(|value| value.is_zero()) {
+            problems.push(TableProblem::DivisionByZero {
+                monkey: monkey.clone(),
+            });
+        }
+    }
+
+    problems
+}
+
+fn visit(
+    expressions: &ExpressionTable,
+    name: &str,
+    visited: &mut HashMap<String, bool>,
+    path: &mut Vec<String>,
+    problems: &mut Vec<TableProblem>,
+) {
+    visited.insert(name.to_string(), false);
+    path.push(name.to_string());
+
+    if let Some(Yell::Expression { left, right, .. }) = expressions.get(name) {
+        for next in [left, right] {
+            match visited.get(next) {
+                Some(false) => {
+                    let start = path.iter().position(|monkey| monkey == next).unwrap();
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(next.clone());
+                    problems.push(TableProblem::Cycle { path: cycle });
+                }
+                Some(true) => {}
+                None if expressions.contains_key(next) => {
+                    visit(expressions, next, visited, path, problems);
+                }
+                None => problems.push(TableProblem::Undefined {
+                    monkey: next.clone(),
+                    referenced_by: Some(name.to_string()),
+                }),
+            }
+        }
+    }
 
-    match value {
-        Yell::Number(n) => Node::Number(n),
-        Yell::Variable => Node::Variable,
+    path.pop();
+    visited.insert(name.to_string(), true);
+}
+
+// the monkey's number, if it can be worked out without any unknowns
+fn constant_value(
+    expressions: &ExpressionTable,
+    name: &str,
+    memo: &mut HashMap<String, Option<Rational>>,
+) -> Option<Rational> {
+    if let Some(&value) = memo.get(name) {
+        return value;
+    }
+    // stops the search from going round a cycle forever
+    memo.insert(name.to_string(), None);
+
+    let value = match expressions.get(name)? {
+        Yell::Number(n) => i128::try_from(*n).ok().map(Rational::from),
+        Yell::Variable => None,
         Yell::Expression {
             left,
             operator,
             right,
-        } => Node::Expression {
-            left: Box::new(parse_tree(expressions, &left)),
-            operator,
-            right: Box::new(parse_tree(expressions, &right)),
-        },
+        } => {
+            let left = constant_value(expressions, left, memo);
+            let right = constant_value(expressions, right, memo);
+            match (left, operator, right) {
+                (Some(left), Operator::Plus, Some(right)) => Some(left + right),
+                (Some(left), Operator::Minus, Some(right)) => Some(left - right),
+                (Some(left), Operator::Multiply, Some(right)) => Some(left * right),
+                (Some(left), Operator::Divide, Some(right)) if !right.is_zero() => {
+                    Some(left / right)
+                }
+                _ => None,
+            }
+        }
+    };
+
+    memo.insert(name.to_string(), value);
+    value
+}
+
+fn parse_with_unknowns(file: &str, unknowns: &[&str]) -> ExpressionTable {
+    let mut expressions = parse_input(file);
+    for unknown in unknowns {
+        expressions.insert((*unknown).to_string(), Yell::Variable);
     }
+    expressions
 }
 
-fn evaluate_tree(node: &Node) -> usize {
-    match node {
-        Node::Number(n) => *n,
-        Node::Variable => panic!("did not expect variable"),
-        Node::Expression {
+// the tree under `root`, once the table has been checked for anything that would stop it from
+// being built. unused monkeys are fine
+fn checked_tree(file: &str, unknowns: &[&str]) -> Result<Node, SolveError> {
+    let expressions = parse_with_unknowns(file, unknowns);
+
+    let problems: Vec<TableProblem> = validate_table(&expressions)
+        .into_iter()
+        .filter(|problem| !matches!(problem, TableProblem::Unused { .. }))
+        .collect();
+    if !problems.is_empty() {
+        return Err(SolveError::InvalidTable(problems));
+    }
+
+    Ok(parse_tree(&expressions, "root"))
+}
+
+fn parse_tree(expressions: &ExpressionTable, key: &str) -> Node {
+    match &expressions[key] {
+        Yell::Number(n) => Node::Number(Rational::from(i128::try_from(*n).unwrap())),
+        Yell::Variable => Node::Variable(key.to_string()),
+        Yell::Expression {
             left,
             operator,
             right,
-        } => {
-            let left = evaluate_tree(left);
-            let right = evaluate_tree(right);
+        } => Node::Expression {
+            left: Box::new(parse_tree(expressions, left)),
+            operator: *operator,
+            right: Box::new(parse_tree(expressions, right)),
+        },
+    }
+}
 
-            match operator {
-                Operator::Plus => left + right,
-                Operator::Minus => left - right,
-                Operator::Multiply => left * right,
-                Operator::Divide => left / right,
-            }
-        }
+fn evaluate_tree(node: &Node) -> Result<Rational, SolveError> {
+    let value = simplify(node)?;
+    if value.is_constant() {
+        Ok(value.constant)
+    } else {
+        Err(SolveError::Unresolved(value))
     }
 }
 
-fn prune_tree(node: Node) -> Node {
+// the tree as a linear expression of its variables
+fn simplify(node: &Node) -> Result<Linear, SolveError> {
     match node {
-        Node::Number(n) => Node::Number(n),
-        Node::Variable => Node::Variable,
+        Node::Number(n) => Ok(Linear::constant(*n)),
+        Node::Variable(name) => Ok(Linear::variable(name)),
         Node::Expression {
             left,
             operator,
             right,
         } => {
-            if node_has_variable(&left) {
-                let left = prune_tree(*left);
-                let right = evaluate_tree(&right);
-                Node::Expression {
-                    left: Box::new(left),
-                    operator,
-                    right: Box::new(Node::Number(right)),
-                }
-            } else if node_has_variable(&right) {
-                let left = evaluate_tree(&left);
-                let right = prune_tree(*right);
-                Node::Expression {
-                    left: Box::new(Node::Number(left)),
-                    operator,
-                    right: Box::new(right),
+            let left = simplify(left)?;
+            let right = simplify(right)?;
+
+            match operator {
+                Operator::Plus => Ok(left.plus(&right)),
+                Operator::Minus => Ok(left.minus(&right)),
+                Operator::Multiply if left.is_constant() => Ok(right.scale(left.constant)),
+                Operator::Multiply if right.is_constant() => Ok(left.scale(right.constant)),
+                Operator::Divide if right.is_constant() => {
+                    if right.constant.is_zero() {
+                        Err(SolveError::DivisionByZero)
+                    } else {
+                        Ok(left.scale(Rational::ONE / right.constant))
+                    }
                 }
-            } else {
-                panic!("neither branch has variable");
+                Operator::Multiply | Operator::Divide => Err(SolveError::NonLinear),
             }
         }
     }
 }
 
-fn solve_for_human_number(
-    // the side of the tree with the variable in it
-    variable_tree: Node,
-    // the number on the other side of the tree we want to make the above equal to
-    number: usize,
-) -> usize {
-    match variable_tree {
-        Node::Number(_) => panic!("not expecting number"),
-        Node::Variable => number,
+// folds every branch without a variable into a single number
+fn prune_tree(node: Node) -> Result<Node, SolveError> {
+    if !node_has_variable(&node) {
+        return Ok(Node::Number(evaluate_tree(&node)?));
+    }
+
+    match node {
         Node::Expression {
             left,
             operator,
             right,
-        } => {
-            if node_has_variable(&left) {
-                let right = evaluate_tree(&right);
-                let new_number = match operator {
-                    Operator::Plus => number - right,
-                    Operator::Minus => number + right,
-                    Operator::Multiply => number / right,
-                    Operator::Divide => number * right,
-                };
-                solve_for_human_number(*left, new_number)
-            } else if node_has_variable(&right) {
-                let left = evaluate_tree(&left);
-                let new_number = match operator {
-                    Operator::Plus => number - left,
-                    Operator::Minus => left - number, // number = left - variable => varaible = left - number
-                    Operator::Multiply => number / left,
-                    Operator::Divide => left / number, // number = left / variable => variable = left / number
-                };
-                solve_for_human_number(*right, new_number)
-            } else {
-                panic!("neither branch has variable");
-            }
-        }
+        } => Ok(Node::Expression {
+            left: Box::new(prune_tree(*left)?),
+            operator,
+            right: Box::new(prune_tree(*right)?),
+        }),
+        leaf => Ok(leaf),
     }
 }
 
 fn node_has_variable(node: &Node) -> bool {
     match node {
         Node::Number(_) => false,
-        Node::Variable => true,
+        Node::Variable(_) => true,
         Node::Expression { left, right, .. } => node_has_variable(left) || node_has_variable(right),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{day21, fetch_input};
+    use crate::day21::{self, Linear, Rational, SolveError, TableProblem};
+    use crate::fetch_input;
 
     #[test]
     fn find_root_number() {
@@ -251,7 +851,7 @@ mod tests {
 
         let tests = vec![
             ("example/day21.txt", 152),
-            ("input/day21.txt", 21120928600114),
+            ("input/day21.txt", 21_120_928_600_114),
         ];
 
         for (infile, want) in tests {
@@ -266,7 +866,7 @@ mod tests {
 
         let tests = vec![
             ("example/day21.txt", 301),
-            ("input/day21.txt", 3453748220116),
+            ("input/day21.txt", 3_453_748_220_116),
         ];
 
         for (infile, want) in tests {
@@ -274,4 +874,168 @@ mod tests {
             assert_eq!(got, want, "got {got}, wanted {want}");
         }
     }
+
+    #[test]
+    fn solve_expressions() {
+        let tests = vec![
+            // the unknown on the right of the equation
+            (
+                "root: a + b\na: 10\nb: c - humn\nc: 4\nhumn: 1",
+                Ok(Linear::constant(Rational::from(-6))),
+            ),
+            (
+                "root: a + b\na: 2\nb: humn * c\nc: 3\nhumn: 1",
+                Err(SolveError::NonInteger(Rational::new(2, 3))),
+            ),
+            ("root: a + b\na: humn * y\nb: 4", Err(SolveError::NonLinear)),
+            // nothing depends on the unknown
+            (
+                "root: a + b\na: 1\nb: 2",
+                Err(SolveError::NoUniqueSolution {
+                    variable: "humn".to_string(),
+                }),
+            ),
+            (
+                "root: 5",
+                Err(SolveError::NoUniqueSolution {
+                    variable: "humn".to_string(),
+                }),
+            ),
+            (
+                "root: a + b\na: humn / z\nz: c - d\nc: 3\nd: 3\nb: 1",
+                Err(SolveError::InvalidTable(vec![
+                    TableProblem::DivisionByZero {
+                        monkey: "a".to_string(),
+                    },
+                ])),
+            ),
+            (
+                "root: a + b\na: humn * c\nc: 0\nb: y + d\nd: 1",
+                Err(SolveError::NoUniqueSolution {
+                    variable: "humn".to_string(),
+                }),
+            ),
+        ];
+
+        for test in tests {
+            let (input, want) = test;
+            let got = day21::solve_expressions(input, &["humn", "y"], "humn").and_then(|human| {
+                human.to_integer()?;
+                Ok(human)
+            });
+            assert_eq!(got, want, "got {got:?}, wanted {want:?}, for {input:?}");
+        }
+
+        // 2x = y + 6
+        let input = "root: a + b\na: x * c\nc: 2\nb: y + d\nd: 6\nx: 0\ny: 0";
+        let got = day21::solve_expressions(input, &["x", "y"], "x").unwrap();
+        assert_eq!(got.to_string(), "1/2·y + 3");
+        assert_eq!(got.to_integer(), Err(SolveError::Unresolved(got.clone())));
+    }
+
+    #[test]
+    fn format_tree() {
+        let tests = vec![
+            (vec![], "152"),
+            (vec!["humn"], "(4 + 2 * (humn - 3)) / 4 = 150"),
+        ];
+
+        for test in tests {
+            let (unknowns, want) = test;
+            let got = day21::format_tree("example/day21.txt", &unknowns).unwrap();
+            assert_eq!(got, want, "got {got}, wanted {want}, for {unknowns:?}");
+        }
+
+        let tests = vec![
+            (
+                "root: a + b\na: x - c\nc: y - d\nd: 1\nb: 2",
+                "x - (y - 1) = 2",
+            ),
+            (
+                "root: a + b\na: c - x\nc: y + d\nd: 1\nb: e / f\ne: 1\nf: 2",
+                "y + 1 - x = 1/2",
+            ),
+            (
+                "root: a + b\na: x * c\nc: d / e\nd: 1\ne: 2\nb: y + g\ng: f - h\nf: 1\nh: 4",
+                "x * 1/2 = y + (-3)",
+            ),
+            (
+                "root: a + b\na: c / x\nc: y * z\nb: e / d\ne: 6\nd: 2",
+                "y * z / x = 3",
+            ),
+        ];
+
+        for test in tests {
+            let (input, want) = test;
+            let got = day21::format_expressions(input, &["x", "y", "z"]).unwrap();
+            assert_eq!(got, want, "got {got}, wanted {want}, for {input:?}");
+        }
+    }
+
+    #[test]
+    fn dependency_graph_dot() {
+        let dot = day21::dependency_graph_dot("example/day21.txt", &["humn"]);
+
+        assert!(dot.starts_with("digraph monkeys {\n"));
+        assert!(dot.contains("    humn [label=\"humn\\n?\" style=bold];\n"));
+        assert!(dot.contains("    ptdq [label=\"ptdq\\n-\" style=bold];\n"));
+        assert!(dot.contains("    sjmn [label=\"sjmn\\n*\"];\n"));
+        assert!(dot.contains("    root -> pppw;\n"));
+        assert_eq!(dot.matches(" -> ").count(), 14);
+    }
+
+    #[test]
+    fn validate() {
+        fetch_input(21);
+
+        for filename in ["example/day21.txt", "input/day21.txt"] {
+            let got = day21::validate(filename, &["humn"]);
+            assert!(got.is_empty(), "got {got:?}, for {filename}");
+        }
+
+        let input = "\
+root: a + b
+a: c / d
+c: 4
+d: e - f
+e: 2
+f: 2
+b: g + zzzz
+g: h * i
+h: 3
+i: g + h
+spare: 7";
+        let to_strings = |names: &[&str]| names.iter().map(ToString::to_string).collect();
+
+        let got = day21::validate_table(&day21::parse_input(input));
+        let want = vec![
+            TableProblem::Cycle {
+                path: to_strings(&["g", "i", "g"]),
+            },
+            TableProblem::Undefined {
+                monkey: "zzzz".to_string(),
+                referenced_by: Some("b".to_string()),
+            },
+            TableProblem::Unused {
+                monkey: "spare".to_string(),
+            },
+            TableProblem::DivisionByZero {
+                monkey: "a".to_string(),
+            },
+        ];
+        assert_eq!(got, want, "got {got:#?}, wanted {want:#?}");
+
+        // unused monkeys don't stop the solver
+        let got = day21::solve_expressions(input, &["c"], "c");
+        let want = Err(SolveError::InvalidTable(vec![
+            want[0].clone(),
+            want[1].clone(),
+            want[3].clone(),
+        ]));
+        assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+
+        // a monkey can be listened to by more than one other
+        let got = day21::format_expressions("root: a + b\na: c * c\nb: c + c\nc: 3", &[]);
+        assert_eq!(got, Ok("15".to_string()));
+    }
 }
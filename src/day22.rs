@@ -79,7 +79,7 @@ impl FlatInput {
                     }
                     ' ' => (),
                     _ => panic!("unexpected input"),
-                };
+                }
             }
         }
 
@@ -405,7 +405,7 @@ fn follow_instructions(mut state: State, input: &impl Input) -> isize {
                 for _ in 0..*n {
                     state.step();
                     match input.get_tile(&state.position) {
-                        Some(Tile::Open) => continue,
+                        Some(Tile::Open) => {}
                         Some(Tile::Wall) => {
                             state.step_back();
                             break;
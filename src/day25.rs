@@ -85,7 +85,7 @@ mod tests {
             (20, "1-0"),
             (2022, "1=11-2"),
             (12345, "1-0---0"),
-            (314159265, "1121-1110-1=0"),
+            (314_159_265, "1121-1110-1=0"),
         ];
         for (n, balanced_quinary_n) in tests {
             assert_eq!(n.to_balanced_quinary(), balanced_quinary_n);
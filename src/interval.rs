@@ -0,0 +1,253 @@
+use std::cmp;
+
+// inclusive interval of integers, empty when start > end
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub start: isize,
+    pub end: isize,
+}
+
+impl Interval {
+    pub fn new(start: isize, end: isize) -> Self {
+        Interval { start, end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start > self.end
+    }
+
+    pub fn length(&self) -> isize {
+        if self.is_empty() {
+            0
+        } else {
+            self.end - self.start + 1
+        }
+    }
+
+    pub fn contains(&self, value: isize) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub fn contains_interval(&self, other: &Interval) -> bool {
+        other.is_empty() || (self.start <= other.start && other.end <= self.end)
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    #[must_use]
+    pub fn intersection(&self, other: &Interval) -> Interval {
+        Interval::new(
+            cmp::max(self.start, other.start),
+            cmp::min(self.end, other.end),
+        )
+    }
+}
+
+// set of integers stored as sorted, disjoint intervals. touching intervals are merged, so that
+// every gap between two stored intervals holds at least one integer
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        if interval.is_empty() {
+            return;
+        }
+
+        // the stored intervals in first..last overlap or touch the new one
+        let first = self
+            .intervals
+            .partition_point(|existing| existing.end.saturating_add(1) < interval.start);
+        let last = self
+            .intervals
+            .partition_point(|existing| existing.start <= interval.end.saturating_add(1));
+
+        let merged = if first < last {
+            Interval::new(
+                cmp::min(interval.start, self.intervals[first].start),
+                cmp::max(interval.end, self.intervals[last - 1].end),
+            )
+        } else {
+            interval
+        };
+
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = self.clone();
+        for interval in &other.intervals {
+            result.insert(*interval);
+        }
+        result
+    }
+
+    #[must_use]
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (left, right) = (self.intervals[i], other.intervals[j]);
+
+            let overlap = left.intersection(&right);
+            if !overlap.is_empty() {
+                intervals.push(overlap);
+            }
+
+            // move past whichever interval finishes first
+            if left.end < right.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    #[must_use]
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+
+        for interval in &self.intervals {
+            let mut start = interval.start;
+
+            let first = other
+                .intervals
+                .partition_point(|removed| removed.end < interval.start);
+
+            for removed in other.intervals[first..]
+                .iter()
+                .take_while(|removed| removed.start <= interval.end)
+            {
+                if removed.start > start {
+                    intervals.push(Interval::new(start, removed.start - 1));
+                }
+                start = removed.end.saturating_add(1);
+            }
+
+            if start <= interval.end {
+                intervals.push(Interval::new(start, interval.end));
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    pub fn contains(&self, value: isize) -> bool {
+        let index = self
+            .intervals
+            .partition_point(|interval| interval.end < value);
+        self.intervals
+            .get(index)
+            .is_some_and(|interval| interval.contains(value))
+    }
+
+    pub fn contains_interval(&self, other: &Interval) -> bool {
+        if other.is_empty() {
+            return true;
+        }
+        let index = self
+            .intervals
+            .partition_point(|interval| interval.end < other.start);
+        self.intervals
+            .get(index)
+            .is_some_and(|interval| interval.contains_interval(other))
+    }
+
+    pub fn total_length(&self) -> isize {
+        self.intervals.iter().map(Interval::length).sum()
+    }
+
+    // the missing stretches between the first and last stored intervals
+    pub fn gaps(&self) -> Vec<Interval> {
+        self.intervals
+            .windows(2)
+            .map(|pair| Interval::new(pair[0].end + 1, pair[1].start - 1))
+            .collect()
+    }
+}
+
+impl From<Interval> for IntervalSet {
+    fn from(interval: Interval) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(interval);
+        set
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Interval>>(iter: T) -> Self {
+        let mut set = IntervalSet::new();
+        for interval in iter {
+            set.insert(interval);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interval::{Interval, IntervalSet};
+
+    fn set(intervals: &[(isize, isize)]) -> IntervalSet {
+        intervals
+            .iter()
+            .map(|&(start, end)| Interval::new(start, end))
+            .collect()
+    }
+
+    #[test]
+    fn insert_merges_overlapping_and_touching() {
+        let got = set(&[(5, 8), (1, 2), (3, 4), (10, 12), (11, 20), (30, 29)]);
+        let want = vec![Interval::new(1, 8), Interval::new(10, 20)];
+
+        assert_eq!(got.intervals(), want);
+        assert_eq!(got.total_length(), 19);
+        assert_eq!(got.gaps(), vec![Interval::new(9, 9)]);
+        assert!(got.contains(10) && !got.contains(9));
+        assert!(got.contains_interval(&Interval::new(2, 7)));
+        assert!(!got.contains_interval(&Interval::new(7, 10)));
+    }
+
+    #[test]
+    fn set_operations() {
+        let left = set(&[(0, 10), (20, 30)]);
+        let right = set(&[(5, 25), (28, 40)]);
+
+        let tests = vec![
+            (left.union(&right), set(&[(0, 40)])),
+            (
+                left.intersection(&right),
+                set(&[(5, 10), (20, 25), (28, 30)]),
+            ),
+            (left.difference(&right), set(&[(0, 4), (26, 27)])),
+            (right.difference(&left), set(&[(11, 19), (31, 40)])),
+        ];
+
+        for test in tests {
+            let (got, want) = test;
+            assert_eq!(got, want, "got {got:?}, wanted {want:?}");
+        }
+    }
+}
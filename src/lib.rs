@@ -31,6 +31,7 @@ pub mod day23;
 pub mod day24;
 pub mod day25;
 
+pub mod interval;
 pub mod queue;
 
 use std::{env, fs, path::Path};
@@ -49,7 +49,7 @@ impl<T: Eq> Ord for MinPriorityQueueItem<T> {
 
 impl<T: Eq> PartialOrd for MinPriorityQueueItem<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(other.priority.cmp(&self.priority))
+        Some(self.cmp(other))
     }
 }
 